@@ -0,0 +1,136 @@
+// Drives a running nilapi with the rinha load mix (credits, debits and
+// statements across customers 1 to 5) and reports latency per operation.
+//
+//   BENCH_URL=http://localhost:8080 cargo run --release --example bench
+//
+// Run it against an instance started with DB_PREPARED_STATEMENTS=true and
+// one with DB_PREPARED_STATEMENTS=false to compare both query paths.
+
+use std::env;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use awc::Client;
+use futures_util::future::join_all;
+
+const CUSTOMERS: u64 = 5;
+
+struct Samples {
+    transactions: Vec<Duration>,
+    statements: Vec<Duration>,
+    errors: u64,
+}
+
+// xorshift, enough to spread the load without pulling in a rng crate
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+async fn worker(url: String, requests: u64, seed: u64) -> Samples {
+    let client = Client::builder().timeout(Duration::from_secs(10)).finish();
+    let mut random = Random(seed | 1);
+    let mut samples = Samples{ transactions: Vec::new(), statements: Vec::new(), errors: 0 };
+
+    for _ in 0..requests {
+        let customer_id = random.next(CUSTOMERS) + 1;
+        let started = Instant::now();
+
+        // four transactions, as many credits as debits, to each statement
+        let (ok, is_statement) = match random.next(10) {
+            0 | 1 => {
+                let response = client
+                    .get(format!("{}/clientes/{}/extrato", url, customer_id))
+                    .send()
+                    .await;
+                (response.is_ok_and(|response| response.status().is_success()), true)
+            }
+            operation => {
+                let transaction_type = if operation % 2 == 0 { "c" } else { "d" };
+                let response = client
+                    .post(format!("{}/clientes/{}/transacoes", url, customer_id))
+                    .send_json(&serde_json::json!({
+                        "valor": random.next(10000) + 1,
+                        "tipo": transaction_type,
+                        "descricao": "bench",
+                    }))
+                    .await;
+                // 422 is the expected answer for a debit over the limit
+                (response.is_ok_and(|response| response.status().as_u16() != 500), false)
+            }
+        };
+
+        let elapsed = started.elapsed();
+
+        if !ok {
+            samples.errors += 1;
+        } else if is_statement {
+            samples.statements.push(elapsed);
+        } else {
+            samples.transactions.push(elapsed);
+        }
+    }
+
+    samples
+}
+
+fn report(name: &str, samples: &mut [Duration]) {
+    if samples.is_empty() {
+        println!("{:<12} no samples", name);
+        return
+    }
+
+    samples.sort();
+    let percentile = |p: f64| samples[((samples.len() - 1) as f64 * p) as usize];
+    let mean = samples.iter().sum::<Duration>() / samples.len() as u32;
+
+    println!(
+        "{:<12} n={:<7} mean={:>9.3?} p50={:>9.3?} p95={:>9.3?} p99={:>9.3?} max={:>9.3?}",
+        name,
+        samples.len(),
+        mean,
+        percentile(0.50),
+        percentile(0.95),
+        percentile(0.99),
+        samples[samples.len() - 1],
+    );
+}
+
+#[actix_web::main]
+async fn main() {
+    let url: String = env_or("BENCH_URL", String::from("http://localhost:9999"));
+    let concurrency: u64 = env_or("BENCH_CONCURRENCY", 32);
+    let requests: u64 = env_or("BENCH_REQUESTS", 20000);
+
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+    let started = Instant::now();
+
+    let workers = (0..concurrency)
+        .map(|index| worker(url.clone(), requests / concurrency, seed.wrapping_add(index * 7919)));
+    let results = join_all(workers).await;
+
+    let elapsed = started.elapsed();
+
+    let mut transactions = Vec::new();
+    let mut statements = Vec::new();
+    let mut errors = 0;
+    for samples in results {
+        transactions.extend(samples.transactions);
+        statements.extend(samples.statements);
+        errors += samples.errors;
+    }
+
+    let total = transactions.len() + statements.len();
+    println!("{} requests in {:.2?} ({:.0} req/s), {} errors", total, elapsed, total as f64 / elapsed.as_secs_f64(), errors);
+    report("transacoes", &mut transactions);
+    report("extrato", &mut statements);
+}
@@ -2,7 +2,9 @@ create table customer (
     id int not null primary key,
     credit_limit bigint not null,
     balance bigint not null constraint balance_check check(balance >= (-1 * credit_limit)),
-    latest_transactions jsonb
+    latest_transactions jsonb,
+    -- set on the old shard once the customer has been rebalanced away
+    moved_to int
 );
 
 create table transactions (
@@ -10,12 +12,230 @@ create table transactions (
     customer_id int not null references customer(id),
     amount bigint not null,
     transaction_type char(1) not null,
-    description varchar(10) not null
+    description varchar(10) not null,
+    category varchar(20),
+    tags varchar(20)[] not null default '{}',
+    metadata jsonb,
+    created_at timestamptz not null default now(),
+    -- only ordered per customer, a rebalanced customer keeps its numbers
+    seq bigserial not null,
+    balance bigint
 );
 
+create index transactions_customer_created_idx on transactions (customer_id, created_at desc);
+create unique index transactions_customer_seq_idx on transactions (customer_id, seq);
+
 insert into customer (id, credit_limit, balance) values
     (1, 100000, 0),
     (2, 80000, 0),
     (3, 1000000, 0),
     (4, 10000000, 0),
     (5, 500000, 0);
+
+create table schedules (
+    id bigserial not null primary key,
+    customer_id int not null references customer(id),
+    amount bigint not null,
+    transaction_type char(1) not null,
+    description varchar(10) not null,
+    recurrence varchar(10) not null,
+    next_run_at timestamptz not null,
+    -- first run, monthly occurrences keep its day of month
+    anchor_at timestamptz not null,
+    retry_at timestamptz,
+    attempts int not null default 0,
+    active boolean not null default true
+);
+
+create index schedules_due_idx on schedules (coalesce(retry_at, next_run_at)) where active;
+
+create table schedule_failures (
+    id bigserial not null primary key,
+    schedule_id bigint not null references schedules(id),
+    run_at timestamptz not null,
+    attempt int not null,
+    reason varchar(32) not null,
+    created_at timestamptz not null default now()
+);
+
+create table spending_rules (
+    customer_id int not null primary key references customer(id),
+    max_debit bigint,
+    max_daily_debit bigint,
+    max_hourly_debits int,
+    blocked_patterns varchar(64)[] not null default '{}'
+);
+
+-- a claimed day stays unposted while the charge does not fit the limit
+create table interest_accruals (
+    customer_id int not null references customer(id),
+    accrual_date date not null,
+    amount bigint not null,
+    posted boolean not null default false,
+    primary key (customer_id, accrual_date)
+);
+
+create index interest_accruals_unposted_idx on interest_accruals (customer_id) where not posted;
+
+-- days every overdrawn customer has been claimed for, kept on the first database
+create table interest_runs (
+    accrual_date date not null primary key,
+    finished_at timestamptz not null default now()
+);
+
+create table outbox (
+    id bigserial not null primary key,
+    customer_id int not null,
+    payload jsonb not null,
+    attempts int not null default 0,
+    next_attempt_at timestamptz not null default now(),
+    last_error varchar(256),
+    created_at timestamptz not null default now()
+);
+
+create index outbox_pending_idx on outbox (next_attempt_at, id);
+
+create table webhooks (
+    customer_id int not null primary key references customer(id),
+    url varchar(512) not null,
+    secret varchar(128) not null
+);
+
+create table webhook_dead_letters (
+    id bigserial not null primary key,
+    event_id bigint not null,
+    customer_id int not null,
+    payload jsonb not null,
+    attempts int not null,
+    last_error varchar(256),
+    failed_at timestamptz not null default now()
+);
+
+create table api_keys (
+    token varchar(64) not null primary key,
+    customer_id int not null references customer(id),
+    created_at timestamptz not null default now()
+);
+
+-- Single round-trip write path (DB_WRITE_MODE=function). Posts a transaction
+-- and its fee with the same checks as the multi-statement path and returns
+-- either an error code or the new limit and balance. Entries arrive in their
+-- latest_transactions cache shape.
+create or replace function create_transaction(
+    p_customer_id int,
+    p_entries jsonb
+) returns table (code varchar, credit_limit bigint, balance bigint)
+language plpgsql as $$
+declare
+    v_limit bigint;
+    v_balance bigint;
+    v_total bigint;
+    v_transaction jsonb := p_entries -> 0;
+    v_created_at timestamptz := (p_entries -> 0 ->> 'created_at')::timestamptz;
+    v_rules spending_rules%rowtype;
+    v_entry jsonb;
+    v_amount bigint;
+    v_seq bigint;
+    v_event jsonb;
+begin
+    select c.credit_limit, c.balance into v_limit, v_balance
+    from customer c
+    where c.id = p_customer_id and c.moved_to is null
+    for update;
+
+    if not found then
+        return query select 'cliente_nao_encontrado'::varchar, null::bigint, null::bigint;
+        return;
+    end if;
+
+    if v_transaction ->> 'transaction_type' = 'd' then
+        select * into v_rules from spending_rules r where r.customer_id = p_customer_id;
+
+        if found then
+            if exists (
+                select 1 from unnest(v_rules.blocked_patterns) as pattern
+                where (v_transaction ->> 'description') ~* pattern
+            ) then
+                return query select 'descricao_bloqueada'::varchar, null::bigint, null::bigint;
+                return;
+            end if;
+
+            if (v_transaction ->> 'amount')::bigint > v_rules.max_debit then
+                return query select 'limite_debito_unico'::varchar, null::bigint, null::bigint;
+                return;
+            end if;
+
+            if v_rules.max_daily_debit is not null and (
+                select coalesce(sum(t.amount), 0) from transactions t
+                where t.customer_id = p_customer_id and t.transaction_type = 'd'
+                    and t.created_at >= date_trunc('day', v_created_at at time zone 'UTC') at time zone 'UTC'
+            ) + (v_transaction ->> 'amount')::bigint > v_rules.max_daily_debit then
+                return query select 'limite_debito_diario'::varchar, null::bigint, null::bigint;
+                return;
+            end if;
+
+            if v_rules.max_hourly_debits is not null and (
+                select count(*) from transactions t
+                where t.customer_id = p_customer_id and t.transaction_type = 'd'
+                    and t.created_at > v_created_at - interval '1 hour'
+            ) + 1 > v_rules.max_hourly_debits then
+                return query select 'limite_debitos_por_hora'::varchar, null::bigint, null::bigint;
+                return;
+            end if;
+        end if;
+    end if;
+
+    -- only credits add to the balance, so checking the final balance covers
+    -- every entry before anything is written
+    select coalesce(sum(case when e ->> 'transaction_type' = 'c'
+        then (e ->> 'amount')::bigint else -(e ->> 'amount')::bigint end), 0)
+    into v_total
+    from jsonb_array_elements(p_entries) as e;
+
+    if v_balance + v_total < -v_limit then
+        return query select 'limite_insuficiente'::varchar, null::bigint, null::bigint;
+        return;
+    end if;
+
+    for v_entry in select e from jsonb_array_elements(p_entries) as e loop
+        v_amount := case when v_entry ->> 'transaction_type' = 'c'
+            then (v_entry ->> 'amount')::bigint else -(v_entry ->> 'amount')::bigint end;
+
+        update customer c
+        set balance = c.balance + v_amount,
+            latest_transactions = v_entry ||
+            case
+                when jsonb_array_length(c.latest_transactions) >= 10 then coalesce(c.latest_transactions - (-1), '[]'::jsonb)
+                else coalesce(c.latest_transactions, '[]')
+            end
+        where c.id = p_customer_id
+        returning c.balance into v_balance;
+
+        insert into transactions (
+            id, customer_id, amount, transaction_type, description,
+            category, tags, metadata, created_at, balance
+        ) values (
+            coalesce((v_entry ->> 'id')::uuid, gen_random_uuid()), p_customer_id, (v_entry ->> 'amount')::bigint,
+            v_entry ->> 'transaction_type', v_entry ->> 'description',
+            v_entry ->> 'category',
+            coalesce(array(select jsonb_array_elements_text(v_entry -> 'tags')), '{}'),
+            v_entry -> 'metadata', (v_entry ->> 'created_at')::timestamptz, v_balance
+        ) returning seq into v_seq;
+
+        v_event := jsonb_build_object(
+            'customer_id', p_customer_id,
+            'amount', (v_entry ->> 'amount')::bigint,
+            'transaction_type', v_entry ->> 'transaction_type',
+            'description', v_entry ->> 'description',
+            'created_at', v_entry -> 'created_at',
+            'limit', v_limit,
+            'balance', v_balance
+        );
+
+        insert into outbox (customer_id, payload) values (p_customer_id, v_event);
+        perform pg_notify('transactions', jsonb_build_object('seq', v_seq, 'event', v_event)::text);
+    end loop;
+
+    return query select null::varchar, v_limit, v_balance;
+end;
+$$;
@@ -0,0 +1,326 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::Config;
+use crate::db::Listener;
+use crate::responses::GetStatementResponse;
+
+struct Entry {
+    response: GetStatementResponse,
+    cached_at: Instant,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<i32, Entry>,
+    // the entries ordered by age, oldest first, for eviction
+    by_age: BTreeSet<(Instant, i32)>,
+    // version of the last invalidation per customer, so a read that raced a
+    // write cannot store what it read before the write
+    invalidated: HashMap<i32, u64>,
+    version: u64,
+    // reads started before this version are never stored
+    floor: u64,
+}
+
+impl State {
+    fn insert(&mut self, customer_id: i32, entry: Entry) {
+        self.remove(customer_id);
+        self.by_age.insert((entry.cached_at, customer_id));
+        self.entries.insert(customer_id, entry);
+    }
+
+    fn remove(&mut self, customer_id: i32) {
+        if let Some(old) = self.entries.remove(&customer_id) {
+            self.by_age.remove(&(old.cached_at, customer_id));
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((_, customer_id)) = self.by_age.pop_first() {
+            self.entries.remove(&customer_id);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.by_age.clear();
+    }
+}
+
+struct Inner {
+    ttl: Duration,
+    max_entries: usize,
+    state: Mutex<State>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+pub struct CacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+// Read-through cache of the latest-transactions statement. Entries are
+// dropped on local writes right after commit and on NOTIFYs from any
+// instance; the TTL bounds staleness while the listener is reconnecting.
+#[derive(Clone)]
+pub struct StatementCache {
+    inner: Option<Arc<Inner>>,
+}
+
+impl StatementCache {
+    pub fn from_config(config: &Config) -> StatementCache {
+        if config.statement_cache_ttl_ms == 0 || config.statement_cache_max_entries == 0 {
+            return StatementCache{ inner: None }
+        }
+
+        StatementCache::new(
+            Duration::from_millis(config.statement_cache_ttl_ms),
+            config.statement_cache_max_entries,
+        )
+    }
+
+    fn new(ttl: Duration, max_entries: usize) -> StatementCache {
+        StatementCache{
+            inner: Some(Arc::new(Inner{
+                ttl,
+                max_entries,
+                state: Mutex::new(State::default()),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            })),
+        }
+    }
+
+    // On a miss returns the version to hand back to `put` once the
+    // statement has been read from the database.
+    pub fn get(&self, customer_id: i32) -> Result<GetStatementResponse, u64> {
+        let Some(inner) = &self.inner else {
+            return Err(0)
+        };
+
+        let mut state = inner.state.lock().unwrap();
+
+        let fresh = state.entries.get(&customer_id)
+            .filter(|entry| entry.cached_at.elapsed() < inner.ttl)
+            .map(|entry| entry.response.clone());
+
+        match fresh {
+            Some(mut response) => {
+                inner.hits.fetch_add(1, Ordering::Relaxed);
+                // the statement date is when it is served, not when it was read
+                response.balance.date = Utc::now();
+                Ok(response)
+            }
+            None => {
+                inner.misses.fetch_add(1, Ordering::Relaxed);
+                state.remove(customer_id);
+                Err(state.version)
+            }
+        }
+    }
+
+    pub fn put(&self, customer_id: i32, version: u64, response: &GetStatementResponse) {
+        let Some(inner) = &self.inner else {
+            return
+        };
+
+        let mut state = inner.state.lock().unwrap();
+
+        let stale = version < state.floor
+            || state.invalidated.get(&customer_id).is_some_and(|invalidated| *invalidated > version);
+        if stale {
+            return
+        }
+
+        if state.entries.len() >= inner.max_entries && !state.entries.contains_key(&customer_id) {
+            state.evict_oldest();
+        }
+
+        state.insert(customer_id, Entry{
+            response: response.clone(),
+            cached_at: Instant::now(),
+        });
+    }
+
+    pub fn invalidate(&self, customer_id: i32) {
+        let Some(inner) = &self.inner else {
+            return
+        };
+
+        let mut state = inner.state.lock().unwrap();
+
+        state.version += 1;
+        let version = state.version;
+        state.remove(customer_id);
+
+        if state.invalidated.len() >= inner.max_entries {
+            state.invalidated.clear();
+            state.floor = version;
+        }
+        state.invalidated.insert(customer_id, version);
+    }
+
+    pub fn invalidate_all(&self) {
+        let Some(inner) = &self.inner else {
+            return
+        };
+
+        let mut state = inner.state.lock().unwrap();
+
+        state.version += 1;
+        state.floor = state.version;
+        state.clear();
+        state.invalidated.clear();
+    }
+
+    // drops entries for every transaction committed by any instance
+    pub fn listen(&self, listener: &Listener) {
+        if self.inner.is_none() {
+            return
+        }
+
+        let cache = self.clone();
+        let mut notifications = listener.subscribe();
+
+        actix_web::rt::spawn(async move {
+            loop {
+                match notifications.recv().await {
+                    Ok(notification) => cache.invalidate(notification.event.customer_id as i32),
+                    Err(RecvError::Lagged(_)) => cache.invalidate_all(),
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let Some(inner) = &self.inner else {
+            return CacheStats{ entries: 0, hits: 0, misses: 0 }
+        };
+
+        CacheStats{
+            entries: inner.state.lock().unwrap().entries.len(),
+            hits: inner.hits.load(Ordering::Relaxed),
+            misses: inner.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::transaction::Customer;
+
+    use super::*;
+
+    fn statement(balance: i64) -> GetStatementResponse {
+        GetStatementResponse::from_customer(&Customer{ limit: 1000, balance, transactions: vec![] })
+    }
+
+    fn cache(max_entries: usize) -> StatementCache {
+        StatementCache::new(Duration::from_secs(60), max_entries)
+    }
+
+    fn miss(cache: &StatementCache, customer_id: i32) -> u64 {
+        cache.get(customer_id).err().expect("expected a miss")
+    }
+
+    fn cached_balance(cache: &StatementCache, customer_id: i32) -> Option<i64> {
+        cache.get(customer_id).ok().map(|response| response.balance.balance)
+    }
+
+    #[test]
+    fn stores_what_a_miss_reads() {
+        let cache = cache(10);
+
+        let version = miss(&cache, 1);
+        cache.put(1, version, &statement(-10));
+
+        assert_eq!(cached_balance(&cache, 1), Some(-10));
+    }
+
+    #[test]
+    fn read_that_raced_a_write_is_not_stored() {
+        let cache = cache(10);
+
+        let version = miss(&cache, 1);
+        cache.invalidate(1);
+        cache.put(1, version, &statement(-10));
+        assert_eq!(cached_balance(&cache, 1), None);
+
+        // a read started after the write is stored
+        let version = miss(&cache, 1);
+        cache.put(1, version, &statement(-20));
+        assert_eq!(cached_balance(&cache, 1), Some(-20));
+    }
+
+    #[test]
+    fn invalidation_only_affects_its_customer() {
+        let cache = cache(10);
+
+        let version = miss(&cache, 2);
+        cache.invalidate(1);
+        cache.put(2, version, &statement(-10));
+
+        assert_eq!(cached_balance(&cache, 2), Some(-10));
+    }
+
+    #[test]
+    fn reads_below_the_floor_are_not_stored() {
+        let cache = cache(10);
+
+        let version = miss(&cache, 2);
+        cache.invalidate_all();
+        cache.put(2, version, &statement(-10));
+        assert_eq!(cached_balance(&cache, 2), None);
+    }
+
+    #[test]
+    fn forgetting_old_invalidations_raises_the_floor() {
+        let cache = cache(2);
+
+        let version = miss(&cache, 3);
+        cache.invalidate(1);
+        cache.invalidate(2);
+        cache.invalidate(4);
+        cache.put(3, version, &statement(-10));
+        assert_eq!(cached_balance(&cache, 3), None);
+    }
+
+    #[test]
+    fn evicts_the_oldest_entry_when_full() {
+        let cache = cache(2);
+
+        for customer_id in 1..=3 {
+            let version = miss(&cache, customer_id);
+            cache.put(customer_id, version, &statement(customer_id as i64));
+        }
+
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cached_balance(&cache, 1), None);
+        assert_eq!(cached_balance(&cache, 2), Some(2));
+        assert_eq!(cached_balance(&cache, 3), Some(3));
+    }
+
+    #[test]
+    fn refreshing_an_entry_moves_it_to_the_back() {
+        let cache = cache(2);
+
+        for customer_id in [1, 2, 1, 3] {
+            cache.invalidate(customer_id);
+            let version = miss(&cache, customer_id);
+            cache.put(customer_id, version, &statement(customer_id as i64));
+        }
+
+        assert_eq!(cached_balance(&cache, 1), Some(1));
+        assert_eq!(cached_balance(&cache, 2), None);
+        assert_eq!(cached_balance(&cache, 3), Some(3));
+    }
+}
@@ -0,0 +1,63 @@
+use actix_http::encoding::Encoder;
+use actix_web::HttpMessage;
+use actix_web::body::{BodySize, EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{AcceptEncoding, ContentEncoding, Encoding};
+
+use crate::config::Config;
+
+const SUPPORTED: [Encoding; 4] = [
+    Encoding::brotli(),
+    Encoding::zstd(),
+    Encoding::gzip(),
+    Encoding::identity(),
+];
+
+// Response compression negotiated from Accept-Encoding. Unlike actix's
+// Compress it leaves bodies under SERVER_COMPRESSION_MIN_BYTES alone, where
+// the encoder costs more than the bytes it saves, and streams like /eventos,
+// whose events would sit in the encoder's buffer.
+#[derive(Clone, Copy)]
+pub struct Compression {
+    enabled: bool,
+    min_bytes: u64,
+}
+
+impl Compression {
+    pub fn from_config(config: &Config) -> Compression {
+        Compression{
+            enabled: config.server_compression,
+            min_bytes: config.server_compression_min_bytes,
+        }
+    }
+
+    pub fn negotiate(&self, req: &ServiceRequest) -> ContentEncoding {
+        if !self.enabled {
+            return ContentEncoding::Identity
+        }
+
+        let Some(accept) = req.get_header::<AcceptEncoding>() else {
+            return ContentEncoding::Identity
+        };
+
+        match accept.negotiate(SUPPORTED.iter()) {
+            Some(Encoding::Known(encoding)) => encoding,
+            _ => ContentEncoding::Identity,
+        }
+    }
+
+    pub fn encode<B: MessageBody>(
+        &self,
+        res: ServiceResponse<B>,
+        encoding: ContentEncoding,
+    ) -> ServiceResponse<EitherBody<B, Encoder<B>>> {
+        let min_bytes = self.min_bytes;
+
+        res.map_body(|head, body| match body.size() {
+            BodySize::Sized(size) if encoding != ContentEncoding::Identity && size >= min_bytes => {
+                EitherBody::right(Encoder::response(encoding, head, body))
+            }
+            _ => EitherBody::left(body),
+        })
+    }
+}
@@ -1,3 +1,3 @@
 mod env;
 
-pub use env::{Config, LOGO};
+pub use env::{Config, DbBackend, WriteMode, LOGO};
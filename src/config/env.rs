@@ -39,11 +39,237 @@ pub const LOGO: &str = "
 
 ";
 
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteMode {
+    // BEGIN, one statement per step, COMMIT
+    #[default]
+    Statements,
+    // the create_transaction PL/pgSQL function, one round trip
+    Function,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DbBackend {
+    #[default]
+    Postgres,
+    // balances and last-10 caches held in process, durable through a local WAL
+    Memory,
+}
+
 #[derive(Deserialize)]
 pub struct Config {
     pub server_url: String,
-    pub db_host: String,
-    pub db_user: String,
-    pub db_pass: String,
-    pub db_name: String,
-}
\ No newline at end of file
+    #[serde(default = "default_server_socket_mode")]
+    pub server_socket_mode: String,
+    #[serde(default)]
+    pub server_http2: bool,
+    pub server_tls_cert: Option<String>,
+    pub server_tls_key: Option<String>,
+    #[serde(default)]
+    pub server_compression: bool,
+    #[serde(default = "default_server_compression_min_bytes")]
+    pub server_compression_min_bytes: u64,
+    // required for postgres unless DB_URL is set, see Config::missing_postgres
+    pub db_host: Option<String>,
+    pub db_user: Option<String>,
+    pub db_pass: Option<String>,
+    pub db_name: Option<String>,
+    #[serde(default = "default_scheduler_interval_ms")]
+    pub scheduler_interval_ms: u64,
+    #[serde(default = "default_scheduler_max_retries")]
+    pub scheduler_max_retries: i32,
+    #[serde(default = "default_scheduler_retry_delay_secs")]
+    pub scheduler_retry_delay_secs: i64,
+    #[serde(default)]
+    pub fees_daily_interest_bps: i64,
+    #[serde(default)]
+    pub fees_debit_fee: i64,
+    #[serde(default = "default_fees_interval_secs")]
+    pub fees_interval_secs: u64,
+    #[serde(default = "default_webhook_interval_ms")]
+    pub webhook_interval_ms: u64,
+    #[serde(default = "default_webhook_max_attempts")]
+    pub webhook_max_attempts: i32,
+    #[serde(default = "default_webhook_backoff_ms")]
+    pub webhook_backoff_ms: i64,
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub webhook_timeout_ms: u64,
+    #[serde(default)]
+    pub webhook_allow_private: bool,
+    pub admin_token: Option<String>,
+    pub grpc_url: Option<String>,
+    #[serde(default)]
+    pub db_write_mode: WriteMode,
+    #[serde(default)]
+    pub db_write_batch_window_ms: u64,
+    #[serde(default = "default_db_write_batch_size")]
+    pub db_write_batch_size: usize,
+    #[serde(default = "default_db_prepared_statements")]
+    pub db_prepared_statements: bool,
+    #[serde(default)]
+    pub db_replica_hosts: Vec<String>,
+    #[serde(default = "default_db_replica_max_lag_ms")]
+    pub db_replica_max_lag_ms: u64,
+    #[serde(default = "default_db_replica_check_interval_ms")]
+    pub db_replica_check_interval_ms: u64,
+    #[serde(default)]
+    pub statement_cache_ttl_ms: u64,
+    #[serde(default = "default_statement_cache_max_entries")]
+    pub statement_cache_max_entries: usize,
+    #[serde(default)]
+    pub statement_raw_json: bool,
+    // sqlite://path selects the embedded backend, DB_HOST and friends are
+    // then unused
+    pub db_url: Option<String>,
+    #[serde(default)]
+    pub db_shard_hosts: Vec<String>,
+    #[serde(default)]
+    pub db_shard_map: Vec<String>,
+    #[serde(default)]
+    pub proxy_upstreams: Vec<String>,
+    #[serde(default = "default_proxy_health_interval_ms")]
+    pub proxy_health_interval_ms: u64,
+    #[serde(default = "default_proxy_timeout_ms")]
+    pub proxy_timeout_ms: u64,
+    #[serde(default)]
+    pub db_backend: DbBackend,
+    #[serde(default = "default_db_wal_path")]
+    pub db_wal_path: String,
+    #[serde(default = "default_db_wal_sync_interval_ms")]
+    pub db_wal_sync_interval_ms: u64,
+    #[serde(default = "default_db_wal_compact_entries")]
+    pub db_wal_compact_entries: u64,
+    #[serde(default = "default_db_memory_customers")]
+    pub db_memory_customers: Vec<String>,
+}
+
+impl Config {
+    // A postgres deployment that lost its DB_* settings must not quietly
+    // connect to a local database instead.
+    pub fn missing_postgres(&self) -> Vec<&'static str> {
+        if self.db_url.is_some() || self.db_backend != DbBackend::Postgres {
+            return vec![]
+        }
+
+        [
+            ("DB_HOST", &self.db_host),
+            ("DB_USER", &self.db_user),
+            ("DB_PASS", &self.db_pass),
+            ("DB_NAME", &self.db_name),
+        ]
+            .into_iter()
+            .filter(|(_, value)| value.is_none())
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    // postgres' own defaults, only reached by the embedded backends, whose
+    // pool is never used
+    pub fn db_host(&self) -> &str {
+        self.db_host.as_deref().unwrap_or("localhost:5432")
+    }
+
+    pub fn db_user(&self) -> &str {
+        self.db_user.as_deref().unwrap_or("postgres")
+    }
+
+    pub fn db_pass(&self) -> &str {
+        self.db_pass.as_deref().unwrap_or_default()
+    }
+
+    pub fn db_name(&self) -> &str {
+        self.db_name.as_deref().unwrap_or("postgres")
+    }
+}
+
+fn default_server_compression_min_bytes() -> u64 {
+    1024
+}
+
+// owner and group, so a proxy in the socket's group can connect
+fn default_server_socket_mode() -> String {
+    String::from("660")
+}
+
+fn default_scheduler_interval_ms() -> u64 {
+    1000
+}
+
+fn default_scheduler_max_retries() -> i32 {
+    3
+}
+
+fn default_scheduler_retry_delay_secs() -> i64 {
+    60
+}
+
+fn default_fees_interval_secs() -> u64 {
+    60
+}
+
+fn default_webhook_interval_ms() -> u64 {
+    500
+}
+
+fn default_webhook_max_attempts() -> i32 {
+    8
+}
+
+fn default_webhook_backoff_ms() -> i64 {
+    1000
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_db_write_batch_size() -> usize {
+    64
+}
+
+fn default_db_prepared_statements() -> bool {
+    true
+}
+
+fn default_db_replica_max_lag_ms() -> u64 {
+    1000
+}
+
+fn default_db_replica_check_interval_ms() -> u64 {
+    500
+}
+
+fn default_statement_cache_max_entries() -> usize {
+    10000
+}
+
+fn default_proxy_health_interval_ms() -> u64 {
+    1000
+}
+
+fn default_proxy_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_db_wal_path() -> String {
+    String::from("nilapi.wal")
+}
+
+fn default_db_wal_sync_interval_ms() -> u64 {
+    2
+}
+
+// 0 only compacts on startup
+fn default_db_wal_compact_entries() -> u64 {
+    100_000
+}
+
+// id:credit_limit pairs, the same customers init-db.sql seeds
+fn default_db_memory_customers() -> Vec<String> {
+    ["1:100000", "2:80000", "3:1000000", "4:10000000", "5:500000"]
+        .iter()
+        .map(|customer| customer.to_string())
+        .collect()
+}
@@ -1,3 +1,24 @@
 mod database;
+mod schedule;
+mod leader;
+mod history;
+mod statement;
+mod spending_rule;
+mod fee;
+mod outbox;
+mod notify;
+mod api_key;
+mod batch;
+mod replica;
+mod prepared;
+mod function;
+mod group_commit;
+mod memory;
+mod wal;
+mod sqlite;
+mod shard;
+mod rebalance;
 
-pub use database::Database;
\ No newline at end of file
+pub use database::Database;
+pub use schedule::ScheduledRun;
+pub use notify::{Listener, RESUME_PAGE};
@@ -0,0 +1,34 @@
+use tokio_postgres::error::SqlState;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::errors::Error::{Default, NotFound};
+
+impl Database {
+    pub async fn create_api_key(&self, customer_id: i32) -> Result<String, Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+        let token = Uuid::new_v4().simple().to_string();
+
+        pg_client.execute(
+            "insert into api_keys (token, customer_id) values ($1::varchar, $2::int)",
+            &[&token, &customer_id],
+        ).await.map_err(|err| match err.code() {
+            Some(&SqlState::FOREIGN_KEY_VIOLATION) => NotFound,
+            _ => Default,
+        })?;
+
+        Ok(token)
+    }
+
+    pub async fn get_customer_by_api_key(&self, token: &str) -> Option<i32> {
+        let pg_client = self.pool.get().await.ok()?;
+
+        let row = pg_client.query_opt(
+            "select customer_id from api_keys where token = $1::varchar",
+            &[&token],
+        ).await.ok()??;
+
+        Some(row.get(0))
+    }
+}
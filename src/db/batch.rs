@@ -0,0 +1,100 @@
+use crate::db::Database;
+use crate::db::database::apply_entry;
+use crate::errors::Error;
+use crate::errors::Error::{Default, NotFound};
+use crate::models::{Transaction, TransactionCache};
+use crate::models::transaction::CustomerLean;
+
+const CACHE_SIZE: i64 = 10;
+
+impl Database {
+    // Applies `items` in order inside one database transaction. In atomic mode
+    // the first failure rolls everything back and ends the batch; otherwise each
+    // item runs in its own savepoint. The last-10 cache is rebuilt once at the end.
+    pub async fn create_transactions_batch(
+        &self,
+        customer_id: i64,
+        items: Vec<Result<Transaction, Error>>,
+        atomic: bool,
+    ) -> Result<Vec<Result<CustomerLean, Error>>, Error> {
+        let mut pg_client = self.customer_client(customer_id as i32).await?;
+        let mut db_transaction = pg_client.transaction().await.map_err(|_| Default)?;
+
+        let customer = db_transaction.query_opt(
+            "select 1 from customer where id = $1::bigint and moved_to is null for update",
+            &[&customer_id],
+        ).await.map_err(|_| Default)?;
+
+        if customer.is_none() {
+            let _ = db_transaction.rollback().await;
+            return Err(NotFound)
+        }
+
+        let mut results = Vec::with_capacity(items.len());
+        let mut posted = vec![];
+
+        for item in items {
+            let transaction = match item {
+                Ok(transaction) => transaction,
+                Err(err) => {
+                    results.push(Err(err));
+                    continue
+                }
+            };
+
+            let result = if atomic {
+                apply_entry(self, &db_transaction, &transaction, false).await
+            } else {
+                // a savepoint that cannot be opened, released or rolled back
+                // leaves the whole transaction aborted, so the batch fails
+                let savepoint = db_transaction.transaction().await.map_err(|_| Default)?;
+                let result = apply_entry(self, &savepoint, &transaction, false).await;
+                if result.is_ok() {
+                    savepoint.commit().await.map_err(|_| Default)?;
+                } else {
+                    savepoint.rollback().await.map_err(|_| Default)?;
+                }
+                result
+            };
+
+            if result.is_err() && atomic {
+                let _ = db_transaction.rollback().await;
+                results.push(result);
+                return Ok(results)
+            }
+
+            if result.is_ok() {
+                posted.push(TransactionCache::from_transaction(&transaction));
+                if let Some(fee) = self.fees.debit_fee_for(&transaction) {
+                    posted.push(TransactionCache::from_transaction(&fee));
+                }
+            }
+
+            results.push(result);
+        }
+
+        if !posted.is_empty() {
+            posted.reverse();
+
+            db_transaction.execute(
+                "update customer \
+                set latest_transactions = ( \
+                    select coalesce(jsonb_agg(entry order by position), '[]'::jsonb) from ( \
+                        select entry, position \
+                        from jsonb_array_elements($1::jsonb || coalesce(latest_transactions, '[]'::jsonb)) \
+                            with ordinality as cached(entry, position) \
+                        order by position \
+                        limit $2::bigint \
+                    ) latest \
+                ) \
+                where id = $3::bigint",
+                &[&serde_json::to_value(&posted).unwrap(), &CACHE_SIZE, &customer_id],
+            ).await.map_err(|_| Default)?;
+        }
+
+        db_transaction.commit().await.map_err(|_| Default)?;
+        self.statements.invalidate(customer_id as i32);
+
+        Ok(results)
+    }
+}
@@ -1,47 +1,159 @@
-use deadpool_postgres::{Pool};
+use deadpool_postgres::{Object, Pool, Transaction as PgTransaction};
 use tokio_postgres::NoTls;
-use uuid::Uuid;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::Type;
 
+use crate::cache::StatementCache;
 use crate::errors::Error;
-use crate::config::Config;
-use crate::errors::Error::{Default, NotFound};
-use crate::models::{Transaction, TransactionCache};
-use crate::models::transaction::{Customer, CustomerLean};
+use crate::config::{Config, DbBackend, WriteMode};
+use crate::errors::Error::{Default, InsufficientLimit, NotFound, RuleViolation};
+use crate::db::spending_rule::check_spending_rules;
+use crate::models::{FeePolicy, Transaction, TransactionCache, TransactionEvent, TransactionNotification};
+use crate::db::notify::TRANSACTIONS_CHANNEL;
+use crate::db::replica::Replicas;
+use crate::db::group_commit::GroupCommit;
+use crate::db::shard::Shards;
+use crate::db::memory::MemoryEngine;
+use crate::db::sqlite::SqliteStore;
+use crate::models::transaction::{Customer, CustomerLean, TRANSACTION_DEBIT};
 
 #[derive(Clone)]
 pub struct Database {
-    pub pool: Pool
+    pub pool: Pool,
+    pub fees: FeePolicy,
+    pub statements: StatementCache,
+    pub replicas: Replicas,
+    pub shards: Shards,
+    pub prepared_statements: bool,
+    pub statement_raw_json: bool,
+    pub write_mode: WriteMode,
+    group_commit: Option<GroupCommit>,
+    storage: Storage,
+}
+
+// Where balances and the last-10 caches live. Everything else (schedules,
+// rules, webhooks, history) is only available on postgres.
+#[derive(Clone)]
+enum Storage {
+    Postgres,
+    Memory(MemoryEngine),
+    Sqlite(SqliteStore),
 }
 
 impl Database {
     pub async fn init(config: &Config) -> Result<Database, ()> {
-        let mut pg_cfg = deadpool_postgres::Config::new();
-        let host = config.db_host.split(":").collect::<Vec<&str>>();
-        pg_cfg.host = Option::from(String::from(host[0]));
-        if host.len() > 1 {
-            pg_cfg.port = Option::from(host[1].parse::<u16>().unwrap());
+        let missing = config.missing_postgres();
+        if !missing.is_empty() {
+            log::error!("postgres needs {} unless DB_URL is set", missing.join(", "));
+            return Err(())
         }
-        pg_cfg.user = Option::from(config.db_user.clone());
-        pg_cfg.password = Option::from(config.db_pass.clone());
-        pg_cfg.dbname = Option::from(config.db_name.clone());
-        pg_cfg.get_pool_config().max_size = 16;
 
-        let pool = pg_cfg.create_pool(None, NoTls).unwrap();
+        let pool = create_pool(config, config.db_host());
+        let shards = Shards::from_config(config, &pool);
+        let fees = FeePolicy::from_config(config);
 
-        let db = Database{ pool };
+        let storage = match (&config.db_url, config.db_backend) {
+            (Some(url), _) => match url.starts_with("sqlite://") {
+                true => match SqliteStore::open(url, fees) {
+                    Ok(store) => Storage::Sqlite(store),
+                    Err(err) => {
+                        log::error!("fail to open {}: {}", url, err);
+                        return Err(())
+                    }
+                },
+                false => {
+                    log::error!("unsupported DB_URL {}, expected sqlite://path", url);
+                    return Err(())
+                }
+            },
+            (None, DbBackend::Postgres) => Storage::Postgres,
+            (None, DbBackend::Memory) => match MemoryEngine::open(config, fees) {
+                Ok(engine) => Storage::Memory(engine),
+                Err(err) => {
+                    log::error!("fail to open wal {}: {}", config.db_wal_path, err);
+                    return Err(())
+                }
+            },
+        };
+
+        let db = Database{
+            pool,
+            fees,
+            statements: StatementCache::from_config(config),
+            replicas: Replicas::from_config(config),
+            shards,
+            prepared_statements: config.db_prepared_statements,
+            statement_raw_json: config.statement_raw_json,
+            write_mode: config.db_write_mode,
+            group_commit: GroupCommit::from_config(config),
+            storage,
+        };
+
+        db.replicas.spawn_monitor(db.pool.clone());
+        if let Some(group_commit) = &db.group_commit {
+            group_commit.spawn(db.clone());
+        }
 
         Ok(db)
     }
 
-    pub async fn get_customer_by_id(&self, customer_id: i32) -> Result<Customer, Error> {
-        let pg_client = self.pool.get().await.unwrap();
+    pub fn is_postgres(&self) -> bool {
+        matches!(self.storage, Storage::Postgres)
+    }
+
+    pub async fn is_ready(&self) -> bool {
+        if !self.is_postgres() {
+            return true
+        }
+
+        let Ok(pg_client) = self.pool.get().await else {
+            return false
+        };
+
+        pg_client.simple_query("select 1").await.is_ok()
+    }
+
+    // `after` is a write LSN the read has to observe, see `read_client`
+    pub async fn get_customer_by_id(&self, customer_id: i32, after: Option<&str>) -> Result<Customer, Error> {
+        match &self.storage {
+            Storage::Memory(engine) => return engine.get_customer(customer_id).await,
+            Storage::Sqlite(store) => return store.get_customer(customer_id).await,
+            Storage::Postgres => {}
+        }
 
-        let row = pg_client.query_one(
+        let pg_client = self.customer_read_client(customer_id, after).await?;
+
+        self.select_customer(&pg_client, customer_id).await
+    }
+
+    // Never served by a replica, for reads that fill the statement cache: a
+    // replica may not have replayed the write whose invalidation the cache
+    // version reflects, and would cache the stale statement under it.
+    pub async fn get_customer_from_primary(&self, customer_id: i32) -> Result<Customer, Error> {
+        match &self.storage {
+            Storage::Memory(engine) => return engine.get_customer(customer_id).await,
+            Storage::Sqlite(store) => return store.get_customer(customer_id).await,
+            Storage::Postgres => {}
+        }
+
+        let pg_client = self.customer_client(customer_id).await?;
+
+        self.select_customer(&pg_client, customer_id).await
+    }
+
+    async fn select_customer(&self, pg_client: &Object, customer_id: i32) -> Result<Customer, Error> {
+        // a customer moved to another shard is fenced off on this one
+        let Ok(statement) = self.prepare(
+            pg_client,
             "select credit_limit, balance, latest_transactions \
             from customer \
-            where id = $1",
-            &[&customer_id],
-        ).await;
+            where id = $1 and moved_to is null",
+            &[Type::INT4],
+        ).await else {
+            return Err(NotFound)
+        };
+
+        let row = pg_client.query_one(&statement, &[&customer_id]).await;
 
         if row.is_err() {
             return Err(NotFound)
@@ -58,19 +170,99 @@ impl Database {
         &self,
         transaction: Transaction,
     ) -> Result<CustomerLean, Error> {
-        let mut pg_client = self.pool.get().await.unwrap();
+        let customer_id = transaction.customer_id as i32;
+        let result = match &self.storage {
+            Storage::Memory(engine) => Some(engine.create_transaction(transaction.clone()).await),
+            Storage::Sqlite(store) => Some(store.create_transaction(transaction.clone()).await),
+            Storage::Postgres => None,
+        };
+        if let Some(result) = result {
+            if result.is_ok() {
+                self.statements.invalidate(customer_id);
+            }
+            return result
+        }
+
+        if self.write_mode == WriteMode::Function {
+            let result = self.create_transaction_function(&transaction).await;
+            if result.is_ok() {
+                self.statements.invalidate(transaction.customer_id as i32);
+            }
+            return result
+        }
+
+        if let Some(group_commit) = &self.group_commit {
+            return group_commit.submit(transaction).await
+        }
+
+        let mut pg_client = self.customer_client(customer_id).await?;
         let db_transaction = pg_client.transaction().await.unwrap();
 
-        let mut operation_amount = transaction.amount;
-        if transaction.transaction_type == "d" {
-            operation_amount = operation_amount * -1;
+        let result = apply_entry(self, &db_transaction, &transaction, true).await;
+
+        if result.is_err() {
+            db_transaction.rollback().await.expect("fail to rollback");
+            return result
         }
 
+        db_transaction.commit().await.expect("fail commit");
+        self.statements.invalidate(customer_id);
+
+        result
+    }
+}
+
+pub(super) fn create_pool(config: &Config, host: &str) -> Pool {
+    let mut pg_cfg = deadpool_postgres::Config::new();
+    let host = host.split(":").collect::<Vec<&str>>();
+    pg_cfg.host = Option::from(String::from(host[0]));
+    if host.len() > 1 {
+        pg_cfg.port = Option::from(host[1].parse::<u16>().unwrap());
+    }
+    pg_cfg.user = Option::from(config.db_user().to_string());
+    pg_cfg.password = Option::from(config.db_pass().to_string());
+    pg_cfg.dbname = Option::from(config.db_name().to_string());
+    pg_cfg.get_pool_config().max_size = 16;
+
+    pg_cfg.create_pool(None, NoTls).unwrap()
+}
+
+// Checks the spending rules of a debit, then posts it together with its fee.
+// Callers own the surrounding transaction and must roll back on error.
+pub(super) async fn apply_entry(
+    db: &Database,
+    db_transaction: &PgTransaction<'_>,
+    transaction: &Transaction,
+    push_cache: bool,
+) -> Result<CustomerLean, Error> {
+    if transaction.transaction_type == TRANSACTION_DEBIT {
+        if let Some(rule) = check_spending_rules(db, db_transaction, transaction).await? {
+            return Err(RuleViolation(rule))
+        }
+    }
+
+    let result = post_to_ledger(db, db_transaction, transaction, push_cache).await?;
+
+    match db.fees.debit_fee_for(transaction) {
+        Some(fee) => post_to_ledger(db, db_transaction, &fee, push_cache).await,
+        None => Ok(result),
+    }
+}
+
+// Applies one entry to the customer balance, the ledger, the event outbox and,
+// unless the caller rebuilds it afterwards, the last-10 cache.
+async fn post_to_ledger(
+    db: &Database,
+    db_transaction: &PgTransaction<'_>,
+    transaction: &Transaction,
+    push_cache: bool,
+) -> Result<CustomerLean, Error> {
+    let result = if push_cache {
         let transaction_json = serde_json::to_value(
-            TransactionCache::from_transaction(&transaction),
+            TransactionCache::from_transaction(transaction),
         ).unwrap();
 
-        let result = db_transaction.query_one("\
+        let Ok(statement) = db.prepare(db_transaction, "\
             update customer \
             set balance = balance + $1::bigint, \
                 latest_transactions = $2::jsonb || \
@@ -78,45 +270,116 @@ impl Database {
                     when jsonb_array_length(latest_transactions) >= 10 then coalesce(latest_transactions - (-1), '[]'::jsonb) \
                     else coalesce(latest_transactions, '[]') \
                 end \
-            where id = $3::bigint \
+            where id = $3::bigint and moved_to is null \
             returning \
             credit_limit, balance",
-   &[&operation_amount, &transaction_json, &transaction.customer_id]
-        ).await;
-
-        if result.is_err() {
-            db_transaction.rollback().await.expect("fail to rollback");
+            &[Type::INT8, Type::JSONB, Type::INT8],
+        ).await else {
+            return Err(Default)
+        };
 
+        db_transaction.query_opt(
+            &statement,
+            &[&transaction.signed_amount(), &transaction_json, &transaction.customer_id]
+        ).await
+    } else {
+        let Ok(statement) = db.prepare(
+            db_transaction,
+            "update customer set balance = balance + $1::bigint \
+            where id = $2::bigint and moved_to is null \
+            returning credit_limit, balance",
+            &[Type::INT8, Type::INT8],
+        ).await else {
             return Err(Default)
-        }
+        };
 
-        let customer_row = result.unwrap();
-
-        let result = db_transaction.execute(
-            "insert into transactions (\
-            id, customer_id, amount, transaction_type, description\
-            ) values (\
-            $1::uuid, $2::bigint, $3::bigint, $4::varchar, $5::varchar\
-            )",
-            &[
-                &Uuid::new_v4(),
-                &transaction.customer_id,
-                &transaction.amount,
-                &transaction.transaction_type.to_string(),
-                &transaction.description,
-            ]
-        ).await;
+        db_transaction.query_opt(
+            &statement,
+            &[&transaction.signed_amount(), &transaction.customer_id]
+        ).await
+    };
 
-        if result.is_err() {
-            db_transaction.rollback().await.expect("fail to rollback");
-            return Err(Default)
-        }
-        
-        db_transaction.commit().await.expect("fail commit");
+    // balance_check is the only constraint the update can violate
+    let customer_row = match result {
+        Ok(Some(row)) => row,
+        Ok(None) => return Err(NotFound),
+        Err(err) if err.code() == Some(&SqlState::CHECK_VIOLATION) => return Err(InsufficientLimit),
+        Err(_) => return Err(Default),
+    };
+
+    let customer = CustomerLean{
+        limit: customer_row.get(0),
+        balance: customer_row.get(1),
+    };
+
+    let Ok(statement) = db.prepare(
+        db_transaction,
+        "insert into transactions (\
+        id, customer_id, amount, transaction_type, description, \
+        category, tags, metadata, created_at, balance\
+        ) values (\
+        $1::uuid, $2::bigint, $3::bigint, $4::varchar, $5::varchar, \
+        $6::varchar, $7::varchar[], $8::jsonb, $9::timestamptz, $10::bigint\
+        ) returning seq",
+        &[
+            Type::UUID, Type::INT8, Type::INT8, Type::VARCHAR, Type::VARCHAR,
+            Type::VARCHAR, Type::VARCHAR_ARRAY, Type::JSONB, Type::TIMESTAMPTZ, Type::INT8,
+        ],
+    ).await else {
+        return Err(Default)
+    };
 
-        Ok(CustomerLean{
-            limit: customer_row.get(0),
-            balance: customer_row.get(1),
-        })
+    let result = db_transaction.query_one(
+        &statement,
+        &[
+            &transaction.id,
+            &transaction.customer_id,
+            &transaction.amount,
+            &transaction.transaction_type.to_string(),
+            &transaction.description,
+            &transaction.category,
+            &transaction.tags,
+            &transaction.metadata,
+            &transaction.created_at,
+            &customer.balance,
+        ]
+    ).await;
+
+    if result.is_err() {
+        return Err(Default)
+    }
+
+    let event = TransactionEvent::from_transaction(transaction, &customer);
+    let notification = TransactionNotification{
+        seq: result.unwrap().get(0),
+        event: event.clone(),
+    };
+
+    // the notification is only delivered by postgres if the transaction commits
+    let Ok(statement) = db.prepare(
+        db_transaction,
+        "with queued as ( \
+            insert into outbox (customer_id, payload) values ($1::int, $2::jsonb) \
+        ) \
+        select pg_notify($3::varchar, $4::varchar)",
+        &[Type::INT4, Type::JSONB, Type::VARCHAR, Type::VARCHAR],
+    ).await else {
+        return Err(Default)
+    };
+
+    let result = db_transaction.execute(
+        &statement,
+        &[
+            &(transaction.customer_id as i32),
+            &serde_json::to_value(&event).unwrap(),
+            &TRANSACTIONS_CHANNEL,
+            &serde_json::to_string(&notification).unwrap(),
+        ],
+    ).await;
+
+    if result.is_err() {
+        return Err(Default)
     }
-}
\ No newline at end of file
+
+    Ok(customer)
+}
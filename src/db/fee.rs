@@ -0,0 +1,141 @@
+use chrono::{NaiveDate, Utc};
+
+use crate::db::Database;
+use crate::db::database::apply_entry;
+use crate::errors::Error;
+use crate::errors::Error::Default;
+use crate::models::fee::interest_entry;
+
+impl Database {
+    // Days still to be claimed, oldest first and up to `until`. A first run
+    // starts at `until` rather than charging for an unknown past.
+    pub async fn get_interest_dates(&self, until: NaiveDate) -> Result<Vec<NaiveDate>, Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        let row = pg_client.query_one(
+            "select max(accrual_date) from interest_runs",
+            &[],
+        ).await.map_err(|_| Default)?;
+
+        let last: Option<NaiveDate> = row.get(0);
+        let start = last.and_then(|last| last.succ_opt()).unwrap_or(until);
+
+        Ok(start.iter_days().take_while(|date| *date <= until).collect())
+    }
+
+    pub async fn finish_interest_run(&self, accrual_date: NaiveDate) -> Result<(), Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        pg_client.execute(
+            "insert into interest_runs (accrual_date) values ($1::date) on conflict do nothing",
+            &[&accrual_date],
+        ).await.map_err(|_| Default)?;
+
+        Ok(())
+    }
+
+    // customers still unclaimed for the day with their balance at its end,
+    // when that balance was negative
+    pub async fn get_overdrawn_customers(&self, accrual_date: NaiveDate) -> Result<Vec<(i32, i64)>, Error> {
+        let mut customers = vec![];
+
+        for (shard, pool) in self.shards.pools().iter().enumerate() {
+            let pg_client = pool.get().await.map_err(|_| Default)?;
+
+            let rows = pg_client.query(
+                "select id, closing_balance from ( \
+                    select c.id, c.balance - coalesce(( \
+                        select sum(case when t.transaction_type = 'c' then t.amount else -t.amount end) \
+                        from transactions t \
+                        where t.customer_id = c.id \
+                            and t.created_at >= ($1::date + 1)::timestamp at time zone 'UTC' \
+                    ), 0)::bigint as closing_balance \
+                    from customer c \
+                    where c.moved_to is null \
+                        and not exists ( \
+                            select 1 from interest_accruals a \
+                            where a.customer_id = c.id and a.accrual_date = $1::date \
+                        ) \
+                ) as closing \
+                where closing_balance < 0",
+                &[&accrual_date],
+            ).await.map_err(|_| Default)?;
+
+            customers.extend(rows.into_iter()
+                .map(|row| (row.get(0), row.get(1)))
+                .filter(|(customer_id, _)| self.shards.owns(shard, *customer_id)));
+        }
+
+        Ok(customers)
+    }
+
+    // returns false when the day was already claimed, so a restart or a
+    // leadership change never charges the same interest twice
+    pub async fn claim_interest_accrual(&self, customer_id: i32, accrual_date: NaiveDate, amount: i64) -> Result<bool, Error> {
+        let pg_client = self.customer_client(customer_id).await?;
+
+        let inserted = pg_client.execute(
+            "insert into interest_accruals (customer_id, accrual_date, amount) \
+            values ($1::int, $2::date, $3::bigint) \
+            on conflict do nothing",
+            &[&customer_id, &accrual_date, &amount],
+        ).await.map_err(|_| Default)?;
+
+        Ok(inserted == 1)
+    }
+
+    // claimed days not posted yet, oldest first, including those of earlier
+    // days that did not fit the limit
+    pub async fn get_unposted_interest_accruals(&self) -> Result<Vec<(i32, NaiveDate)>, Error> {
+        let mut accruals = vec![];
+
+        for (shard, pool) in self.shards.pools().iter().enumerate() {
+            let pg_client = pool.get().await.map_err(|_| Default)?;
+
+            let rows = pg_client.query(
+                "select customer_id, accrual_date from interest_accruals \
+                where not posted \
+                order by accrual_date",
+                &[],
+            ).await.map_err(|_| Default)?;
+
+            accruals.extend(rows.into_iter()
+                .map(|row| (row.get(0), row.get(1)))
+                .filter(|(customer_id, _)| self.shards.owns(shard, *customer_id)));
+        }
+
+        Ok(accruals)
+    }
+
+    // Marks the accrual posted in the same transaction that charges it, so a
+    // crash in between can neither lose nor repeat the charge. Interest that
+    // would take the balance past the limit fails the balance check and the
+    // accrual stays unposted for the next run.
+    pub async fn post_interest_accrual(&self, customer_id: i32, accrual_date: NaiveDate) -> Result<(), Error> {
+        let mut pg_client = self.customer_client(customer_id).await?;
+        let db_transaction = pg_client.transaction().await.map_err(|_| Default)?;
+
+        let claimed = db_transaction.query_opt(
+            "update interest_accruals set posted = true \
+            where customer_id = $1::int and accrual_date = $2::date and not posted \
+            returning amount",
+            &[&customer_id, &accrual_date],
+        ).await.map_err(|_| Default)?;
+
+        let Some(claimed) = claimed else {
+            return Ok(())
+        };
+
+        let interest = interest_entry(customer_id as i64, claimed.get("amount"), Utc::now());
+
+        if let Err(err) = apply_entry(self, &db_transaction, &interest, true).await {
+            let _ = db_transaction.rollback().await;
+            return Err(err)
+        }
+
+        db_transaction.commit().await.map_err(|_| Default)?;
+        self.statements.invalidate(customer_id);
+
+        Ok(())
+    }
+}
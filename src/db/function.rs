@@ -0,0 +1,56 @@
+use tokio_postgres::types::Type;
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::errors::Error::{Default, InsufficientLimit, NotFound, RuleViolation};
+use crate::models::{SpendingRule, Transaction, TransactionCache};
+use crate::models::transaction::CustomerLean;
+
+impl Database {
+    // One round trip: the `create_transaction` function installed by
+    // init-db.sql checks and posts the transaction and its fee in the
+    // implicit transaction of a single statement.
+    pub(super) async fn create_transaction_function(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<CustomerLean, Error> {
+        let pg_client = self.customer_client(transaction.customer_id as i32).await?;
+
+        let mut entries = vec![TransactionCache::from_transaction(transaction)];
+        if let Some(fee) = self.fees.debit_fee_for(transaction) {
+            entries.push(TransactionCache::from_transaction(&fee));
+        }
+
+        let Ok(statement) = self.prepare(
+            &pg_client,
+            "select code, credit_limit, balance from create_transaction($1::int, $2::jsonb)",
+            &[Type::INT4, Type::JSONB],
+        ).await else {
+            return Err(Default)
+        };
+
+        let row = pg_client.query_one(
+            &statement,
+            &[&(transaction.customer_id as i32), &serde_json::to_value(&entries).unwrap()],
+        ).await;
+
+        let Ok(row) = row else {
+            return Err(Default)
+        };
+
+        let code: Option<String> = row.get("code");
+
+        match code.as_deref() {
+            None => Ok(CustomerLean{
+                limit: row.get("credit_limit"),
+                balance: row.get("balance"),
+            }),
+            Some(code) if code == NotFound.code() => Err(NotFound),
+            Some(code) if code == InsufficientLimit.code() => Err(InsufficientLimit),
+            Some(code) => match SpendingRule::from_code(code) {
+                Some(rule) => Err(RuleViolation(rule)),
+                None => Err(Default),
+            },
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::time::Instant;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::db::database::apply_entry;
+use crate::errors::Error;
+use crate::errors::Error::Default;
+use crate::models::Transaction;
+use crate::models::transaction::CustomerLean;
+
+const WORKERS: usize = 4;
+
+struct PendingWrite {
+    transaction: Transaction,
+    reply: oneshot::Sender<Result<CustomerLean, Error>>,
+}
+
+// Collects concurrent writes for up to DB_WRITE_BATCH_WINDOW_MS and commits
+// them together, each in its own savepoint so one failure only fails its
+// own request.
+#[derive(Clone)]
+pub struct GroupCommit {
+    sender: mpsc::Sender<PendingWrite>,
+    receiver: Arc<Mutex<mpsc::Receiver<PendingWrite>>>,
+    window: Duration,
+    max_size: usize,
+}
+
+impl GroupCommit {
+    pub fn from_config(config: &Config) -> Option<GroupCommit> {
+        if config.db_write_batch_window_ms == 0 || config.db_write_batch_size <= 1 {
+            return None
+        }
+
+        let (sender, receiver) = mpsc::channel(config.db_write_batch_size * WORKERS);
+
+        Some(GroupCommit{
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            window: Duration::from_millis(config.db_write_batch_window_ms),
+            max_size: config.db_write_batch_size,
+        })
+    }
+
+    pub fn spawn(&self, db: Database) {
+        for _ in 0..WORKERS {
+            let group_commit = self.clone();
+            let db = db.clone();
+
+            actix_web::rt::spawn(async move {
+                while let Some(batch) = group_commit.collect().await {
+                    for batch in split_by_shard(&db, batch) {
+                        commit_batch(&db, batch).await;
+                    }
+                }
+            });
+        }
+    }
+
+    pub async fn submit(&self, transaction: Transaction) -> Result<CustomerLean, Error> {
+        let (reply, result) = oneshot::channel();
+
+        if self.sender.send(PendingWrite{ transaction, reply }).await.is_err() {
+            return Err(Default)
+        }
+
+        result.await.unwrap_or(Err(Default))
+    }
+
+    // waits for a first write, then gathers whatever else arrives in the window
+    async fn collect(&self) -> Option<Vec<PendingWrite>> {
+        let mut receiver = self.receiver.lock().await;
+
+        let first = receiver.recv().await?;
+        let deadline = Instant::now() + self.window;
+        let mut batch = vec![first];
+
+        while batch.len() < self.max_size {
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(pending)) => batch.push(pending),
+                _ => break,
+            }
+        }
+
+        Some(batch)
+    }
+}
+
+// a database transaction cannot span shards, each one commits its own part
+fn split_by_shard(db: &Database, batch: Vec<PendingWrite>) -> Vec<Vec<PendingWrite>> {
+    let mut shards: Vec<Vec<PendingWrite>> = (0..db.shards.count()).map(|_| Vec::new()).collect();
+
+    for pending in batch {
+        shards[db.shards.index_for(pending.transaction.customer_id as i32)].push(pending);
+    }
+
+    shards.retain(|batch| !batch.is_empty());
+    shards
+}
+
+// every write in the batch belongs to the same shard
+async fn commit_batch(db: &Database, mut batch: Vec<PendingWrite>) {
+    // customers are locked in id order, so concurrent batches cannot deadlock;
+    // the sort is stable and keeps each customer's writes in arrival order
+    batch.sort_by_key(|pending| pending.transaction.customer_id);
+
+    // a connection or transaction error fails this batch's writes, never the
+    // worker, which would leave submit waiting on a channel nobody reads
+    let Ok(mut pg_client) = db.customer_client(batch[0].transaction.customer_id as i32).await else {
+        return fail(batch)
+    };
+    let Ok(mut db_transaction) = pg_client.transaction().await else {
+        return fail(batch)
+    };
+
+    let mut results = Vec::with_capacity(batch.len());
+
+    for pending in &batch {
+        let Ok(savepoint) = db_transaction.transaction().await else {
+            results.push(Err(Default));
+            continue
+        };
+
+        let result = match apply_entry(db, &savepoint, &pending.transaction, true).await {
+            Ok(customer) => savepoint.commit().await.map(|_| customer).map_err(|_| Default),
+            Err(err) => {
+                let _ = savepoint.rollback().await;
+                Err(err)
+            }
+        };
+
+        results.push(result);
+    }
+
+    let committed = db_transaction.commit().await.is_ok();
+
+    for (pending, result) in batch.into_iter().zip(results) {
+        let result = if committed { result } else { Err(Default) };
+
+        if result.is_ok() {
+            db.statements.invalidate(pending.transaction.customer_id as i32);
+        }
+
+        let _ = pending.reply.send(result);
+    }
+}
+
+fn fail(batch: Vec<PendingWrite>) {
+    for pending in batch {
+        let _ = pending.reply.send(Err(Default));
+    }
+}
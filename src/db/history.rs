@@ -0,0 +1,74 @@
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use tokio_postgres::types::{ToSql, Type};
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::errors::Error::{Default, NotFound};
+use crate::models::TransactionCache;
+use crate::models::transaction::TransactionFilter;
+
+impl Database {
+    pub async fn get_transactions(
+        &self,
+        customer_id: i32,
+        filter: &TransactionFilter,
+        after: Option<&str>,
+    ) -> Result<Vec<TransactionCache>, Error> {
+        self.stream_transactions(customer_id, filter, after).await?.try_collect().await
+    }
+
+    // Yields the transactions as postgres sends the rows; the stream owns the
+    // connection until it is dropped.
+    pub async fn stream_transactions(
+        &self,
+        customer_id: i32,
+        filter: &TransactionFilter,
+        after: Option<&str>,
+    ) -> Result<impl Stream<Item = Result<TransactionCache, Error>> + Send + 'static, Error> {
+        // only implemented on postgres, the other backends serve the last 10
+        if !self.is_postgres() {
+            return Err(Error::Invalid)
+        }
+
+        let pg_client = self.customer_read_client(customer_id, after).await?;
+
+        let exists = self.prepare(
+            &pg_client,
+            "select 1 from customer where id = $1 and moved_to is null",
+            &[Type::INT4],
+        ).await.map_err(|_| Default)?;
+
+        pg_client.query_opt(&exists, &[&customer_id]).await
+            .map_err(|_| Default)?
+            .ok_or(NotFound)?;
+
+        let statement = self.prepare(
+            &pg_client,
+            "select amount, transaction_type, description, category, tags, metadata, created_at \
+            from transactions \
+            where customer_id = $1::int \
+                and ($2::varchar is null or category = $2::varchar) \
+                and ($3::varchar is null or $3::varchar = any(tags)) \
+                and ($4::varchar is null or metadata ->> $4::varchar = $5::varchar) \
+            order by created_at desc \
+            limit $6::bigint",
+            &[Type::INT4, Type::VARCHAR, Type::VARCHAR, Type::VARCHAR, Type::VARCHAR, Type::INT8],
+        ).await.map_err(|_| Default)?;
+
+        let params: [&(dyn ToSql + Sync); 6] = [
+            &customer_id,
+            &filter.category,
+            &filter.tag,
+            &filter.metadata_key,
+            &filter.metadata_value,
+            &filter.limit,
+        ];
+
+        let rows = pg_client.query_raw(&statement, params).await.map_err(|_| Default)?;
+
+        Ok(rows.map(move |row| {
+            let _ = &pg_client;
+            row.map(TransactionCache::from).map_err(|_| Default)
+        }))
+    }
+}
@@ -0,0 +1,38 @@
+use deadpool_postgres::{ClientWrapper, Object};
+
+use crate::db::Database;
+
+impl Database {
+    // Session advisory locks live as long as the connection holding them, so the
+    // winning client is detached from the pool and kept by the caller; dropping it
+    // closes the connection and hands leadership to the other instance.
+    pub async fn try_acquire_leadership(&self, key: i64) -> Option<ClientWrapper> {
+        let pg_client = self.pool.get().await.ok()?;
+
+        let row = pg_client.query_one(
+            "select pg_try_advisory_lock($1::bigint)",
+            &[&key],
+        ).await.ok()?;
+
+        let acquired: bool = row.get(0);
+        if !acquired {
+            return None
+        }
+
+        Some(Object::take(pg_client))
+    }
+
+    pub async fn hold_leadership(&self, leader: &mut Option<ClientWrapper>, key: i64) -> bool {
+        if let Some(client) = leader {
+            if client.simple_query("select 1").await.is_ok() {
+                return true
+            }
+            log::warn!("lost leadership connection for lock {}", key);
+            *leader = None;
+        }
+
+        *leader = self.try_acquire_leadership(key).await;
+
+        leader.is_some()
+    }
+}
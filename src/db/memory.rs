@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::config::Config;
+use crate::db::wal::{Ledger, Wal};
+use crate::errors::Error;
+use crate::errors::Error::{Default, InsufficientLimit, NotFound};
+use crate::models::{FeePolicy, Transaction};
+use crate::models::transaction::{Customer, CustomerLean};
+
+enum Command {
+    Post {
+        transaction: Transaction,
+        reply: oneshot::Sender<Result<CustomerLean, Error>>,
+    },
+    Get {
+        reply: oneshot::Sender<Customer>,
+    },
+}
+
+struct Account {
+    limit: i64,
+    ledger: Ledger,
+}
+
+// Authoritative balances held in process. Each customer is owned by one task
+// that serialises its writes, so the limit check needs no locking; every
+// posted entry goes through the WAL and replies wait for its fsync. Being in
+// process, the balances are only right with a single instance, see Wal.
+#[derive(Clone)]
+pub struct MemoryEngine {
+    accounts: Arc<HashMap<i32, mpsc::UnboundedSender<Command>>>,
+}
+
+impl MemoryEngine {
+    pub fn open(config: &Config, fees: FeePolicy) -> std::io::Result<MemoryEngine> {
+        let mut accounts = parse_customers(&config.db_memory_customers);
+
+        let (wal, ledgers) = Wal::open(
+            &config.db_wal_path,
+            Duration::from_millis(config.db_wal_sync_interval_ms),
+            config.db_wal_compact_entries,
+        )?;
+
+        for (customer_id, ledger) in ledgers {
+            match accounts.get_mut(&customer_id) {
+                Some(account) => account.ledger = ledger,
+                None => log::warn!("wal entries for unknown customer {}", customer_id),
+            }
+        }
+
+        let accounts = accounts.into_iter()
+            .map(|(customer_id, account)| {
+                let (sender, receiver) = mpsc::unbounded_channel();
+                actix_web::rt::spawn(run(account, receiver, wal.clone(), fees));
+                (customer_id, sender)
+            })
+            .collect();
+
+        Ok(MemoryEngine{ accounts: Arc::new(accounts) })
+    }
+
+    pub async fn create_transaction(&self, transaction: Transaction) -> Result<CustomerLean, Error> {
+        let (reply, response) = oneshot::channel();
+        self.send(transaction.customer_id as i32, Command::Post{ transaction, reply })?;
+
+        response.await.unwrap_or(Err(Default))
+    }
+
+    pub async fn get_customer(&self, customer_id: i32) -> Result<Customer, Error> {
+        let (reply, response) = oneshot::channel();
+        self.send(customer_id, Command::Get{ reply })?;
+
+        response.await.map_err(|_| Default)
+    }
+
+    fn send(&self, customer_id: i32, command: Command) -> Result<(), Error> {
+        let Some(account) = self.accounts.get(&customer_id) else {
+            return Err(NotFound)
+        };
+
+        account.send(command).map_err(|_| Default)
+    }
+}
+
+impl Account {
+    fn lean(&self) -> CustomerLean {
+        CustomerLean{ limit: self.limit, balance: self.ledger.balance }
+    }
+}
+
+async fn run(
+    mut account: Account,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    wal: Wal,
+    fees: FeePolicy,
+) {
+    while let Some(command) = commands.recv().await {
+        match command {
+            Command::Post{ transaction, reply } => {
+                let mut entries = vec![transaction];
+                if let Some(fee) = fees.debit_fee_for(&entries[0]) {
+                    entries.push(fee);
+                }
+
+                let total: i64 = entries.iter().map(Transaction::signed_amount).sum();
+                if account.ledger.balance + total < -account.limit {
+                    let _ = reply.send(Err(InsufficientLimit));
+                    continue
+                }
+
+                for entry in &entries {
+                    account.ledger.post(entry);
+                }
+
+                let result = account.lean();
+                let last = entries.pop().unwrap();
+                for entry in &entries {
+                    wal.append(entry, None);
+                }
+                wal.append(&last, Some(Box::new(move || { let _ = reply.send(Ok(result)); })));
+            }
+            Command::Get{ reply } => {
+                let customer = Customer{
+                    limit: account.limit,
+                    balance: account.ledger.balance,
+                    transactions: account.ledger.latest.iter().cloned().collect(),
+                };
+
+                // reads never show an entry that could still be lost in a crash
+                wal.barrier(Box::new(move || { let _ = reply.send(customer); }));
+            }
+        }
+    }
+}
+
+fn parse_customers(customers: &[String]) -> HashMap<i32, Account> {
+    customers.iter()
+        .map(|customer| {
+            let (id, limit) = customer.split_once(':')
+                .and_then(|(id, limit)| Some((id.trim().parse().ok()?, limit.trim().parse().ok()?)))
+                .unwrap_or_else(|| panic!("invalid DB_MEMORY_CUSTOMERS entry {:?}, expected id:limit", customer));
+
+            (id, Account{ limit, ledger: Ledger::default() })
+        })
+        .collect()
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{stream, StreamExt};
+use tokio::sync::broadcast;
+use tokio_postgres::{AsyncMessage, NoTls};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::errors::Error;
+use crate::errors::Error::Default;
+use crate::models::TransactionNotification;
+
+pub const TRANSACTIONS_CHANNEL: &str = "transactions";
+
+const CHANNEL_CAPACITY: usize = 1024;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+// a resumed stream reads the missed events in pages of this size
+pub const RESUME_PAGE: i64 = 1000;
+
+// Fans out NOTIFYs from every nilapi instance to local subscribers. Events
+// published while the connection is down are lost here, subscribers recover
+// them from the transactions table.
+#[derive(Clone)]
+pub struct Listener {
+    sender: broadcast::Sender<Arc<TransactionNotification>>,
+}
+
+impl Listener {
+    pub fn spawn(config: &Config) -> Listener {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        // every shard notifies for the customers it holds
+        for host in std::iter::once(config.db_host()).chain(config.db_shard_hosts.iter().map(String::as_str)) {
+            let pg_cfg = listener_config(config, host);
+            let task_sender = sender.clone();
+
+            actix_web::rt::spawn(async move {
+                loop {
+                    if let Err(err) = listen(&pg_cfg, &task_sender).await {
+                        log::warn!("transactions listener disconnected: {}", err);
+                    }
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            });
+        }
+
+        Listener{ sender }
+    }
+
+    // a listener nothing publishes to, for backends without postgres
+    pub fn idle() -> Listener {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Listener{ sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<TransactionNotification>> {
+        self.sender.subscribe()
+    }
+}
+
+fn listener_config(config: &Config, host: &str) -> tokio_postgres::Config {
+    let mut pg_cfg = tokio_postgres::Config::new();
+    let host = host.split(':').collect::<Vec<&str>>();
+    pg_cfg.host(host[0]);
+    if host.len() > 1 {
+        pg_cfg.port(host[1].parse::<u16>().unwrap());
+    }
+    pg_cfg.user(config.db_user());
+    pg_cfg.password(config.db_pass());
+    pg_cfg.dbname(config.db_name());
+
+    pg_cfg
+}
+
+async fn listen(
+    pg_cfg: &tokio_postgres::Config,
+    sender: &broadcast::Sender<Arc<TransactionNotification>>,
+) -> Result<(), tokio_postgres::Error> {
+    let (client, mut connection) = pg_cfg.connect(NoTls).await?;
+
+    // the connection has to be polled for LISTEN itself to complete
+    let sender = sender.clone();
+    let driver = actix_web::rt::spawn(async move {
+        let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+
+        while let Some(message) = messages.next().await {
+            if let AsyncMessage::Notification(notification) = message? {
+                match serde_json::from_str::<TransactionNotification>(notification.payload()) {
+                    Ok(parsed) => { let _ = sender.send(Arc::new(parsed)); }
+                    Err(err) => log::warn!("invalid transaction notification: {}", err),
+                }
+            }
+        }
+
+        Ok(())
+    });
+
+    client.batch_execute(&format!("listen {}", TRANSACTIONS_CHANNEL)).await?;
+
+    driver.await.unwrap_or(Ok(()))
+}
+
+impl Database {
+    pub async fn get_notifications_since(&self, customer_id: i32, seq: i64) -> Result<Vec<TransactionNotification>, Error> {
+        let pg_client = self.customer_client(customer_id).await?;
+
+        let rows = pg_client.query(
+            "select t.seq, t.customer_id, t.amount, t.transaction_type, t.description, \
+                t.created_at, t.balance, c.credit_limit \
+            from transactions t \
+            join customer c on c.id = t.customer_id \
+            where t.customer_id = $1::int and t.seq > $2::bigint and c.moved_to is null \
+            order by t.seq \
+            limit $3::bigint",
+            &[&customer_id, &seq, &RESUME_PAGE],
+        ).await.map_err(|_| Default)?;
+
+        Ok(rows.into_iter().map(TransactionNotification::from).collect())
+    }
+}
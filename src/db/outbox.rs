@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::errors::Error::{Default, NotFound};
+use crate::models::{DeadLetter, OutboxEvent, Webhook};
+
+impl Database {
+    // Outbox rows are written on the customer's shard while webhooks are kept
+    // on shard 0, so each shard's pending events are matched to their webhook
+    // in a second query.
+    pub async fn get_pending_events(&self, limit: i64) -> Result<Vec<OutboxEvent>, Error> {
+        let mut events = vec![];
+
+        for (shard, pool) in self.shards.pools().iter().enumerate() {
+            let pg_client = pool.get().await.map_err(|_| Default)?;
+
+            let rows = pg_client.query(
+                "select id, customer_id, payload, attempts, \
+                    null::varchar as url, null::varchar as secret \
+                from outbox \
+                where next_attempt_at <= now() \
+                order by id \
+                limit $1::bigint",
+                &[&limit],
+            ).await.map_err(|_| Default)?;
+
+            events.extend(rows.into_iter().map(|row| OutboxEvent{ shard, ..OutboxEvent::from(row) }));
+        }
+
+        if events.is_empty() {
+            return Ok(events)
+        }
+
+        let customer_ids = events.iter().map(|event| event.customer_id).collect::<Vec<i32>>();
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        let webhooks = pg_client.query(
+            "select customer_id, url, secret from webhooks where customer_id = any($1::int[])",
+            &[&customer_ids],
+        ).await.map_err(|_| Default)?;
+
+        for event in events.iter_mut() {
+            if let Some(webhook) = webhooks.iter().find(|row| row.get::<_, i32>("customer_id") == event.customer_id) {
+                event.url = webhook.get("url");
+                event.secret = webhook.get("secret");
+            }
+        }
+
+        Ok(events)
+    }
+
+    // delivered events, and events of customers without a webhook, are dropped
+    pub async fn complete_event(&self, event: &OutboxEvent) -> Result<(), Error> {
+        let pg_client = self.shard_client(event.shard).await?;
+
+        pg_client.execute(
+            "delete from outbox where id = $1::bigint",
+            &[&event.id],
+        ).await.map_err(|_| Default)?;
+
+        Ok(())
+    }
+
+    pub async fn reschedule_event(&self, event: &OutboxEvent, next_attempt_at: DateTime<Utc>, error: &str) -> Result<(), Error> {
+        let pg_client = self.shard_client(event.shard).await?;
+
+        pg_client.execute(
+            "update outbox \
+            set attempts = attempts + 1, next_attempt_at = $2::timestamptz, last_error = left($3::varchar, 256) \
+            where id = $1::bigint",
+            &[&event.id, &next_attempt_at, &error],
+        ).await.map_err(|_| Default)?;
+
+        Ok(())
+    }
+
+    // the dead letter stays on the shard of its outbox row
+    pub async fn dead_letter_event(&self, event: &OutboxEvent, error: &str) -> Result<(), Error> {
+        let pg_client = self.shard_client(event.shard).await?;
+
+        pg_client.execute(
+            "with failed as ( \
+                delete from outbox where id = $1::bigint \
+                returning id, customer_id, payload, attempts \
+            ) \
+            insert into webhook_dead_letters (event_id, customer_id, payload, attempts, last_error) \
+            select id, customer_id, payload, attempts + 1, left($2::varchar, 256) from failed",
+            &[&event.id, &error],
+        ).await.map_err(|_| Default)?;
+
+        Ok(())
+    }
+
+    pub async fn get_dead_letters(&self, limit: i64) -> Result<Vec<DeadLetter>, Error> {
+        let mut dead_letters = vec![];
+
+        for (shard, pool) in self.shards.pools().iter().enumerate() {
+            let pg_client = pool.get().await.map_err(|_| Default)?;
+
+            let rows = pg_client.query(
+                "select id, event_id, customer_id, attempts, last_error, failed_at \
+                from webhook_dead_letters \
+                order by id \
+                limit $1::bigint",
+                &[&limit],
+            ).await.map_err(|_| Default)?;
+
+            dead_letters.extend(rows.into_iter().map(|row| DeadLetter{ shard, ..DeadLetter::from(row) }));
+        }
+
+        dead_letters.sort_by_key(|dead_letter| dead_letter.failed_at);
+        dead_letters.truncate(limit as usize);
+
+        Ok(dead_letters)
+    }
+
+    // puts dead letters back in the outbox with a fresh retry budget, keeping
+    // the original event id so receivers can deduplicate
+    pub async fn replay_dead_letters(&self, shard: Option<usize>, dead_letter_id: Option<i64>) -> Result<u64, Error> {
+        let mut replayed = 0;
+
+        for (index, pool) in self.shards.pools().iter().enumerate() {
+            if shard.is_some_and(|shard| shard != index) {
+                continue
+            }
+
+            let pg_client = pool.get().await.map_err(|_| Default)?;
+
+            replayed += pg_client.execute(
+                "with replayed as ( \
+                    delete from webhook_dead_letters \
+                    where $1::bigint is null or id = $1::bigint \
+                    returning event_id, customer_id, payload \
+                ) \
+                insert into outbox (id, customer_id, payload) \
+                select event_id, customer_id, payload from replayed",
+                &[&dead_letter_id],
+            ).await.map_err(|_| Default)?;
+        }
+
+        Ok(replayed)
+    }
+
+    pub async fn save_webhook(&self, customer_id: i32, webhook: &Webhook) -> Result<(), Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        let result = pg_client.execute(
+            "insert into webhooks (customer_id, url, secret) \
+            values ($1::int, $2::varchar, $3::varchar) \
+            on conflict (customer_id) do update set url = excluded.url, secret = excluded.secret",
+            &[&customer_id, &webhook.url, &webhook.secret],
+        ).await;
+
+        if result.is_err() {
+            return Err(NotFound)
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_webhook(&self, customer_id: i32) -> Result<(), Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        let deleted = pg_client.execute(
+            "delete from webhooks where customer_id = $1::int",
+            &[&customer_id],
+        ).await.unwrap_or_default();
+
+        if deleted == 0 {
+            return Err(NotFound)
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use deadpool_postgres::GenericClient;
+use tokio_postgres::Statement;
+use tokio_postgres::types::Type;
+
+use crate::db::Database;
+
+impl Database {
+    // Hot queries are prepared once per pooled connection and reused by name,
+    // saving the parse/plan round trip an unnamed statement costs on every
+    // call. DB_PREPARED_STATEMENTS=false keeps that round trip, for comparison.
+    pub(super) async fn prepare<C: GenericClient>(
+        &self,
+        client: &C,
+        query: &str,
+        types: &[Type],
+    ) -> Result<Statement, tokio_postgres::Error> {
+        if self.prepared_statements {
+            client.prepare_typed_cached(query, types).await
+        } else {
+            client.prepare_typed(query, types).await
+        }
+    }
+}
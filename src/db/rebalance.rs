@@ -0,0 +1,129 @@
+use crate::db::Database;
+
+// Per customer tables that travel with the customer. Schedules, webhooks and
+// api keys stay on shard 0; pending outbox rows and dead letters stay behind
+// on the source, where the webhook job still delivers them.
+const MOVED_TABLES: [&str; 2] = ["spending_rules", "interest_accruals"];
+
+impl Database {
+    // Moves a customer from the shard it is mapped to onto `target` and
+    // returns how many ledger rows moved. The source row stays locked until
+    // the end, so no write can slip in between copy and fence; after the
+    // move the source refuses the customer, with 404s, until DB_SHARD_MAP
+    // points every instance at the new shard. A failed or interrupted move
+    // can be rerun.
+    pub async fn rebalance(&self, customer_id: i32, target: usize) -> Result<i64, String> {
+        let source = self.shards.index_for(customer_id);
+
+        let Some(target_pool) = self.shards.pool(target) else {
+            return Err(format!("unknown shard {}, there are {}", target, self.shards.count()))
+        };
+        if target == source {
+            return Err(format!("customer {} already lives on shard {}", customer_id, target))
+        }
+
+        let mut source_client = self.shards.pool(source).unwrap().get().await.map_err(|err| err.to_string())?;
+        let mut target_client = target_pool.get().await.map_err(|err| err.to_string())?;
+        let source_transaction = source_client.transaction().await.map_err(|err| err.to_string())?;
+        let target_transaction = target_client.transaction().await.map_err(|err| err.to_string())?;
+
+        let Some(customer) = source_transaction.query_opt(
+            "select credit_limit, balance, latest_transactions from customer \
+            where id = $1::int and moved_to is null \
+            for update",
+            &[&customer_id],
+        ).await.map_err(|err| err.to_string())? else {
+            return Err(format!("customer {} not found on shard {}", customer_id, source))
+        };
+
+        let ledger = source_transaction.query_one(
+            "select coalesce(jsonb_agg(t order by t.seq), '[]'), count(*), coalesce(sum(t.amount), 0)::bigint, \
+                coalesce(max(t.seq), 0) \
+            from transactions t where t.customer_id = $1::int",
+            &[&customer_id],
+        ).await.map_err(|err| err.to_string())?;
+        let rows: serde_json::Value = ledger.get(0);
+        let count: i64 = ledger.get(1);
+        let total: i64 = ledger.get(2);
+        let max_seq: i64 = ledger.get(3);
+
+        target_transaction.execute(
+            "insert into customer (id, credit_limit, balance, latest_transactions) \
+            values ($1::int, $2::bigint, $3::bigint, $4::jsonb) \
+            on conflict (id) do update set \
+                credit_limit = excluded.credit_limit, \
+                balance = excluded.balance, \
+                latest_transactions = excluded.latest_transactions, \
+                moved_to = null",
+            &[&customer_id, &customer.get::<_, i64>(0), &customer.get::<_, i64>(1), &customer.get::<_, Option<serde_json::Value>>(2)],
+        ).await.map_err(|err| err.to_string())?;
+
+        // leftovers of an earlier stay or an interrupted move. seq is kept, it
+        // is the SSE event id clients resume from, and the target sequence is
+        // moved past it so later entries still sort after the copied ones.
+        target_transaction.execute(
+            "delete from transactions where customer_id = $1::int",
+            &[&customer_id],
+        ).await.map_err(|err| err.to_string())?;
+        target_transaction.execute(
+            "insert into transactions (\
+            id, customer_id, amount, transaction_type, description, \
+            category, tags, metadata, created_at, seq, balance\
+            ) select \
+            id, customer_id, amount, transaction_type, description, \
+            category, tags, metadata, created_at, seq, balance \
+            from jsonb_populate_recordset(null::transactions, $1::jsonb) \
+            order by seq",
+            &[&rows],
+        ).await.map_err(|err| err.to_string())?;
+        target_transaction.execute(
+            "select setval('transactions_seq_seq', greatest(last_value, $1::bigint)) \
+            from transactions_seq_seq",
+            &[&max_seq],
+        ).await.map_err(|err| err.to_string())?;
+
+        for table in MOVED_TABLES {
+            let moved = source_transaction.query_one(
+                &format!("select coalesce(jsonb_agg(r), '[]') from {} r where r.customer_id = $1::int", table),
+                &[&customer_id],
+            ).await.map_err(|err| err.to_string())?;
+            let moved: serde_json::Value = moved.get(0);
+
+            target_transaction.execute(
+                &format!("delete from {} where customer_id = $1::int", table),
+                &[&customer_id],
+            ).await.map_err(|err| err.to_string())?;
+            target_transaction.execute(
+                &format!("insert into {0} select * from jsonb_populate_recordset(null::{0}, $1::jsonb)", table),
+                &[&moved],
+            ).await.map_err(|err| err.to_string())?;
+        }
+
+        let copied = target_transaction.query_one(
+            "select count(*), coalesce(sum(amount), 0)::bigint from transactions where customer_id = $1::int",
+            &[&customer_id],
+        ).await.map_err(|err| err.to_string())?;
+        if copied.get::<_, i64>(0) != count || copied.get::<_, i64>(1) != total {
+            return Err(String::from("copied ledger does not match the source, nothing was moved"))
+        }
+
+        target_transaction.commit().await.map_err(|err| err.to_string())?;
+
+        // from here on the target holds a full copy, so a failure below only
+        // leaves the source serving the customer and the move can be rerun
+        source_transaction.execute(
+            "update customer set moved_to = $2::int where id = $1::int",
+            &[&customer_id, &(target as i32)],
+        ).await.map_err(|err| err.to_string())?;
+        for table in std::iter::once("transactions").chain(MOVED_TABLES) {
+            source_transaction.execute(
+                &format!("delete from {} where customer_id = $1::int", table),
+                &[&customer_id],
+            ).await.map_err(|err| err.to_string())?;
+        }
+
+        source_transaction.commit().await.map_err(|err| err.to_string())?;
+
+        Ok(count)
+    }
+}
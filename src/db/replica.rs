@@ -0,0 +1,174 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use deadpool_postgres::{Object, Pool};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::db::database::create_pool;
+use crate::errors::Error;
+use crate::errors::Error::Default;
+
+struct Replica {
+    host: String,
+    pool: Pool,
+    healthy: AtomicBool,
+}
+
+struct Inner {
+    replicas: Vec<Replica>,
+    next: AtomicUsize,
+    max_lag_ms: f64,
+    check_interval: Duration,
+}
+
+// Read-only pools to streaming replicas. A replica only serves reads while
+// its last lag check was within DB_REPLICA_MAX_LAG_MS.
+#[derive(Clone)]
+pub struct Replicas {
+    inner: Arc<Inner>,
+}
+
+impl Replicas {
+    pub fn from_config(config: &Config) -> Replicas {
+        let replicas = config.db_replica_hosts
+            .iter()
+            .filter(|host| !host.is_empty())
+            .map(|host| Replica{
+                host: host.clone(),
+                pool: create_pool(config, host),
+                healthy: AtomicBool::new(false),
+            })
+            .collect();
+
+        Replicas{
+            inner: Arc::new(Inner{
+                replicas,
+                next: AtomicUsize::new(0),
+                max_lag_ms: config.db_replica_max_lag_ms as f64,
+                check_interval: Duration::from_millis(config.db_replica_check_interval_ms),
+            }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.inner.replicas.is_empty()
+    }
+
+    pub fn spawn_monitor(&self, primary: Pool) {
+        if !self.is_enabled() {
+            return
+        }
+
+        let inner = self.inner.clone();
+
+        actix_web::rt::spawn(async move {
+            let mut ticker = tokio::time::interval(inner.check_interval);
+
+            loop {
+                ticker.tick().await;
+
+                let primary_lsn = current_wal_lsn(&primary).await;
+
+                for replica in &inner.replicas {
+                    let lag_ms = replication_lag_ms(&replica.pool, primary_lsn.as_deref()).await;
+                    let healthy = lag_ms.is_some_and(|lag_ms| lag_ms <= inner.max_lag_ms);
+
+                    if replica.healthy.swap(healthy, Ordering::Relaxed) == healthy {
+                        continue
+                    }
+
+                    if healthy {
+                        log::info!("replica {} is serving reads", replica.host);
+                    } else {
+                        log::warn!("replica {} stopped serving reads (lag {:?} ms)", replica.host, lag_ms);
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn current_wal_lsn(pool: &Pool) -> Option<String> {
+    let pg_client = pool.get().await.ok()?;
+
+    pg_client.query_one("select pg_current_wal_lsn()::varchar", &[])
+        .await
+        .ok()
+        .map(|row| row.get(0))
+}
+
+// An idle primary writes no WAL, so a replica that replayed up to the
+// primary's position is not lagging however old its last replayed transaction
+// is. Having replayed all it received proves nothing: a replica that lost its
+// connection has nothing left to replay. Without the primary's position the
+// lag falls back to the age of the last replayed transaction.
+async fn replication_lag_ms(pool: &Pool, primary_lsn: Option<&str>) -> Option<f64> {
+    let pg_client = pool.get().await.ok()?;
+
+    let row = pg_client.query_one(
+        "select case \
+            when not pg_is_in_recovery() then null \
+            when pg_last_wal_replay_lsn() >= $1::varchar::pg_lsn then 0::float8 \
+            else extract(epoch from now() - pg_last_xact_replay_timestamp())::float8 * 1000 \
+        end",
+        &[&primary_lsn],
+    ).await.ok()?;
+
+    row.get(0)
+}
+
+impl Database {
+    // Picks a healthy replica for a read, or the primary when there is none.
+    // With `after`, the replica must have replayed up to that write LSN; one
+    // that cannot tell, or a malformed LSN, sends the read to the primary.
+    pub(super) async fn read_client(&self, after: Option<&str>) -> Result<Object, Error> {
+        let inner = &self.replicas.inner;
+        let count = inner.replicas.len();
+        let start = inner.next.fetch_add(1, Ordering::Relaxed);
+
+        for offset in 0..count {
+            let replica = &inner.replicas[(start + offset) % count];
+
+            if !replica.healthy.load(Ordering::Relaxed) {
+                continue
+            }
+
+            let Ok(pg_client) = replica.pool.get().await else {
+                replica.healthy.store(false, Ordering::Relaxed);
+                continue
+            };
+
+            let Some(after) = after else {
+                return Ok(pg_client)
+            };
+
+            let caught_up = pg_client.query_one(
+                "select coalesce(pg_last_wal_replay_lsn() >= $1::varchar::pg_lsn, false)",
+                &[&after],
+            ).await;
+
+            if matches!(caught_up, Ok(row) if row.get::<_, bool>(0)) {
+                return Ok(pg_client)
+            }
+        }
+
+        self.pool.get().await.map_err(|_| Default)
+    }
+
+    // The primary's WAL position once a write has committed, for clients that
+    // want to read it back through a replica.
+    pub async fn current_lsn(&self) -> Option<String> {
+        if !self.replicas.is_enabled() {
+            return None
+        }
+
+        let pg_client = self.pool.get().await.ok()?;
+
+        pg_client.query_one("select pg_current_wal_lsn()::varchar", &[])
+            .await
+            .ok()
+            .map(|row| row.get(0))
+    }
+}
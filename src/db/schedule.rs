@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use tokio_postgres::error::SqlState;
+
+use crate::db::Database;
+use crate::db::database::apply_entry;
+use crate::errors::Error;
+use crate::errors::Error::{Default, NotFound};
+use crate::models::{Schedule, Transaction};
+use crate::models::transaction::TRANSACTION_DEBIT;
+
+pub enum ScheduledRun {
+    Posted,
+    // an earlier run posted the entry but did not get to advance the schedule
+    AlreadyPosted,
+    InsufficientLimit,
+}
+
+impl Database {
+    pub async fn create_schedule(&self, schedule: Schedule) -> Result<Schedule, Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        let row = pg_client.query_one(
+            "insert into schedules (\
+            customer_id, amount, transaction_type, description, recurrence, next_run_at, anchor_at\
+            ) values (\
+            $1::int, $2::bigint, $3::varchar, $4::varchar, $5::varchar, $6::timestamptz, $7::timestamptz\
+            ) returning id",
+            &[
+                &schedule.customer_id,
+                &schedule.amount,
+                &schedule.transaction_type,
+                &schedule.description,
+                &schedule.recurrence.as_str(),
+                &schedule.next_run_at,
+                &schedule.anchor_at,
+            ],
+        ).await.map_err(|err| match err.code() {
+            Some(&SqlState::FOREIGN_KEY_VIOLATION) => NotFound,
+            _ => Default,
+        })?;
+
+        Ok(Schedule{
+            id: row.get(0),
+            ..schedule
+        })
+    }
+
+    pub async fn get_due_schedules(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<Schedule>, Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        let rows = pg_client.query(
+            "select id, customer_id, amount, transaction_type, description, \
+            recurrence, next_run_at, anchor_at, attempts \
+            from schedules \
+            where active and coalesce(retry_at, next_run_at) <= $1::timestamptz \
+            order by coalesce(retry_at, next_run_at) \
+            limit $2::bigint",
+            &[&now, &limit],
+        ).await.map_err(|_| Default)?;
+
+        Ok(rows.into_iter().map(Schedule::from).collect())
+    }
+
+    // Schedules live on shard 0 and the entry on the customer's shard, so the
+    // two can't share a transaction. Instead the entry id is derived from the
+    // occurrence and checked here, in the posting transaction: a run repeated
+    // after a crash or a leadership change before the schedule was advanced
+    // finds its entry and only advances.
+    pub async fn post_scheduled(&self, transaction: &Transaction) -> Result<ScheduledRun, Error> {
+        let customer_id = transaction.customer_id as i32;
+        let mut pg_client = self.customer_client(customer_id).await?;
+        let db_transaction = pg_client.transaction().await.map_err(|_| Default)?;
+
+        let posted = db_transaction.query_one(
+            "select exists(select 1 from transactions where id = $1::uuid)",
+            &[&transaction.id],
+        ).await.map_err(|_| Default)?;
+
+        if posted.get::<_, bool>(0) {
+            let _ = db_transaction.rollback().await;
+            return Ok(ScheduledRun::AlreadyPosted)
+        }
+
+        // checked up front under the row lock, the ledger reports a limit
+        // violation like any other failed update
+        let customer = db_transaction.query_opt(
+            "select balance, credit_limit from customer \
+            where id = $1::int and moved_to is null for update",
+            &[&customer_id],
+        ).await.map_err(|_| Default)?.ok_or(NotFound)?;
+
+        if transaction.transaction_type == TRANSACTION_DEBIT {
+            let fee = self.fees.debit_fee_for(transaction).map_or(0, |fee| fee.amount);
+            let balance: i64 = customer.get(0);
+            let limit: i64 = customer.get(1);
+
+            if balance - transaction.amount - fee < -limit {
+                let _ = db_transaction.rollback().await;
+                return Ok(ScheduledRun::InsufficientLimit)
+            }
+        }
+
+        if let Err(err) = apply_entry(self, &db_transaction, transaction, true).await {
+            let _ = db_transaction.rollback().await;
+            return Err(err)
+        }
+
+        db_transaction.commit().await.map_err(|_| Default)?;
+        self.statements.invalidate(customer_id);
+
+        Ok(ScheduledRun::Posted)
+    }
+
+    // moves the schedule to its next occurrence, deactivating it when there is none
+    pub async fn advance_schedule(&self, schedule: &Schedule, next_run_at: Option<DateTime<Utc>>) -> Result<(), Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        pg_client.execute(
+            "update schedules \
+            set next_run_at = coalesce($2::timestamptz, next_run_at), \
+                retry_at = null, \
+                attempts = 0, \
+                active = $3::boolean \
+            where id = $1::bigint",
+            &[&schedule.id, &next_run_at, &next_run_at.is_some()],
+        ).await.map_err(|_| Default)?;
+
+        Ok(())
+    }
+
+    pub async fn retry_schedule(&self, schedule: &Schedule, retry_at: DateTime<Utc>) -> Result<(), Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        pg_client.execute(
+            "update schedules \
+            set retry_at = $2::timestamptz, \
+                attempts = attempts + 1 \
+            where id = $1::bigint",
+            &[&schedule.id, &retry_at],
+        ).await.map_err(|_| Default)?;
+
+        Ok(())
+    }
+
+    pub async fn log_schedule_failure(&self, schedule: &Schedule, reason: &str) -> Result<(), Error> {
+        let pg_client = self.pool.get().await.map_err(|_| Default)?;
+
+        pg_client.execute(
+            "insert into schedule_failures (\
+            schedule_id, run_at, attempt, reason\
+            ) values (\
+            $1::bigint, $2::timestamptz, $3::int, $4::varchar\
+            )",
+            &[&schedule.id, &schedule.next_run_at, &(schedule.attempts + 1), &reason],
+        ).await.map_err(|_| Default)?;
+
+        Ok(())
+    }
+}
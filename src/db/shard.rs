@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use deadpool_postgres::{Object, Pool};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::db::database::create_pool;
+use crate::errors::Error;
+use crate::errors::Error::Default;
+
+// Customers spread over several postgres databases. Shard 0 is DB_HOST,
+// which also keeps everything that is not per customer (schedules, webhooks,
+// api keys); DB_SHARD_HOSTS adds shards 1..n. A customer lives on the shard
+// DB_SHARD_MAP pins it to, otherwise on id % n. Its ledger, outbox, dead
+// letters and interest accruals live there too, so the jobs reading them
+// walk every shard.
+#[derive(Clone)]
+pub struct Shards {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    pools: Vec<Pool>,
+    pinned: HashMap<i32, usize>,
+}
+
+impl Shards {
+    pub fn from_config(config: &Config, primary: &Pool) -> Shards {
+        let pools = std::iter::once(primary.clone())
+            .chain(config.db_shard_hosts.iter().map(|host| create_pool(config, host)))
+            .collect::<Vec<Pool>>();
+
+        let pinned = config.db_shard_map.iter()
+            .map(|entry| {
+                let (customer_id, shard) = entry.split_once(':')
+                    .and_then(|(customer_id, shard)| Some((customer_id.trim().parse().ok()?, shard.trim().parse().ok()?)))
+                    .filter(|(_, shard)| *shard < pools.len())
+                    .unwrap_or_else(|| panic!("invalid DB_SHARD_MAP entry {:?}, expected customer_id:shard", entry));
+
+                (customer_id, shard)
+            })
+            .collect();
+
+        Shards{ inner: Arc::new(Inner{ pools, pinned }) }
+    }
+
+    pub fn count(&self) -> usize {
+        self.inner.pools.len()
+    }
+
+    pub fn index_for(&self, customer_id: i32) -> usize {
+        match self.inner.pinned.get(&customer_id) {
+            Some(shard) => *shard,
+            None => customer_id.rem_euclid(self.count() as i32) as usize,
+        }
+    }
+
+    pub fn pool(&self, index: usize) -> Option<&Pool> {
+        self.inner.pools.get(index)
+    }
+
+    pub fn pools(&self) -> &[Pool] {
+        &self.inner.pools
+    }
+
+    // init-db seeds every customer on every shard, rows of customers living
+    // elsewhere are stale copies
+    pub fn owns(&self, index: usize, customer_id: i32) -> bool {
+        self.index_for(customer_id) == index
+    }
+}
+
+impl Database {
+    // The connection for a write, or any read that must see the latest one.
+    pub(super) async fn customer_client(&self, customer_id: i32) -> Result<Object, Error> {
+        let index = self.shards.index_for(customer_id);
+        self.shards.inner.pools[index].get().await.map_err(|_| Default)
+    }
+
+    pub(super) async fn shard_client(&self, index: usize) -> Result<Object, Error> {
+        self.shards.inner.pools[index].get().await.map_err(|_| Default)
+    }
+
+    // Replicas only follow DB_HOST, customers on the other shards are read
+    // from their shard directly.
+    pub(super) async fn customer_read_client(&self, customer_id: i32, after: Option<&str>) -> Result<Object, Error> {
+        match self.shards.index_for(customer_id) {
+            0 => self.read_client(after).await,
+            _ => self.customer_client(customer_id).await,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use deadpool_postgres::Transaction as PgTransaction;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::Type;
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::errors::Error::{Default, Invalid, NotFound};
+use crate::models::{SpendingRule, SpendingRules, Transaction};
+
+impl Database {
+    pub async fn get_spending_rules(&self, customer_id: i32) -> Result<SpendingRules, Error> {
+        let pg_client = self.customer_client(customer_id).await?;
+
+        let row = pg_client.query_opt(
+            "select r.max_debit, r.max_daily_debit, r.max_hourly_debits, \
+                coalesce(r.blocked_patterns, '{}') as blocked_patterns \
+            from customer c \
+            left join spending_rules r on r.customer_id = c.id \
+            where c.id = $1::int and c.moved_to is null",
+            &[&customer_id],
+        ).await.map_err(|_| Default)?;
+
+        match row {
+            Some(row) => Ok(SpendingRules::from(row)),
+            None => Err(NotFound),
+        }
+    }
+
+    pub async fn save_spending_rules(&self, customer_id: i32, rules: &SpendingRules) -> Result<(), Error> {
+        let mut pg_client = self.customer_client(customer_id).await?;
+        let db_transaction = pg_client.transaction().await.map_err(|_| Default)?;
+
+        // the share lock waits out a rebalance of the customer, which then
+        // fails the moved_to check instead of writing to the old shard
+        let result = db_transaction.execute(
+            "insert into spending_rules (\
+            customer_id, max_debit, max_daily_debit, max_hourly_debits, blocked_patterns\
+            ) select \
+            c.id, $2::bigint, $3::bigint, $4::int, $5::varchar[] \
+            from customer c \
+            where c.id = $1::int and c.moved_to is null \
+            for share of c \
+            on conflict (customer_id) do update set \
+                max_debit = excluded.max_debit, \
+                max_daily_debit = excluded.max_daily_debit, \
+                max_hourly_debits = excluded.max_hourly_debits, \
+                blocked_patterns = excluded.blocked_patterns",
+            &[
+                &customer_id,
+                &rules.max_debit,
+                &rules.max_daily_debit,
+                &rules.max_hourly_debits,
+                &rules.blocked_patterns,
+            ],
+        ).await;
+
+        match result {
+            Ok(0) => {
+                let _ = db_transaction.rollback().await;
+                return Err(NotFound)
+            }
+            Ok(_) => {}
+            Err(_) => {
+                let _ = db_transaction.rollback().await;
+                return Err(Default)
+            }
+        }
+
+        // patterns are evaluated by postgres on every debit, so a broken one
+        // has to be rejected here rather than fail transactions later
+        let patterns_ok = db_transaction.execute(
+            "select '' ~* pattern from unnest($1::varchar[]) as pattern",
+            &[&rules.blocked_patterns],
+        ).await;
+
+        if let Err(err) = patterns_ok {
+            let _ = db_transaction.rollback().await;
+            return match err.code() {
+                Some(&SqlState::INVALID_REGULAR_EXPRESSION) => Err(Invalid),
+                _ => Err(Default),
+            }
+        }
+
+        db_transaction.commit().await.map_err(|_| Default)?;
+
+        Ok(())
+    }
+}
+
+// Locks the customer row so that concurrent debits are checked against each
+// other, then evaluates every configured rule for the incoming debit. The
+// totals are read by a second statement: under read committed a statement
+// sees the data as of its own start, so totals computed next to the lock
+// would miss a debit committed while this one waited for it. A rule that
+// can't be read fails the debit rather than letting it through.
+pub async fn check_spending_rules(
+    db: &Database,
+    db_transaction: &PgTransaction<'_>,
+    transaction: &Transaction,
+) -> Result<Option<SpendingRule>, Error> {
+    let statement = db.prepare(
+        db_transaction,
+        "select r.max_debit, r.max_daily_debit, r.max_hourly_debits, \
+            exists (select 1 from unnest(r.blocked_patterns) as pattern where $2::varchar ~* pattern) as blocked \
+        from customer c \
+        join spending_rules r on r.customer_id = c.id \
+        where c.id = $1::bigint and c.moved_to is null \
+        for update of c",
+        &[Type::INT8, Type::VARCHAR],
+    ).await.map_err(|_| Default)?;
+
+    let row = db_transaction.query_opt(
+        &statement,
+        &[&transaction.customer_id, &transaction.description],
+    ).await.map_err(|_| Default)?;
+
+    let Some(row) = row else {
+        return Ok(None)
+    };
+
+    let max_debit: Option<i64> = row.get("max_debit");
+    let max_daily_debit: Option<i64> = row.get("max_daily_debit");
+    let max_hourly_debits: Option<i32> = row.get("max_hourly_debits");
+    let blocked: bool = row.get("blocked");
+
+    if blocked {
+        return Ok(Some(SpendingRule::BlockedDescription))
+    }
+
+    if max_debit.is_some_and(|max| transaction.amount > max) {
+        return Ok(Some(SpendingRule::MaxDebit))
+    }
+
+    if max_daily_debit.is_none() && max_hourly_debits.is_none() {
+        return Ok(None)
+    }
+
+    let statement = db.prepare(
+        db_transaction,
+        "select \
+            coalesce(sum(t.amount) filter (where t.created_at >= \
+                date_trunc('day', $2::timestamptz at time zone 'UTC') at time zone 'UTC'), 0)::bigint as daily_total, \
+            count(*) filter (where t.created_at > $2::timestamptz - interval '1 hour') as hourly_count \
+        from transactions t \
+        where t.customer_id = $1::int and t.transaction_type = 'd' \
+            and t.created_at >= least( \
+                date_trunc('day', $2::timestamptz at time zone 'UTC') at time zone 'UTC', \
+                $2::timestamptz - interval '1 hour' \
+            )",
+        &[Type::INT4, Type::TIMESTAMPTZ],
+    ).await.map_err(|_| Default)?;
+
+    let totals = db_transaction.query_one(
+        &statement,
+        &[&(transaction.customer_id as i32), &transaction.created_at],
+    ).await.map_err(|_| Default)?;
+
+    let daily_total: i64 = totals.get("daily_total");
+    let hourly_count: i64 = totals.get("hourly_count");
+
+    if max_daily_debit.is_some_and(|max| daily_total + transaction.amount > max) {
+        return Ok(Some(SpendingRule::MaxDailyDebit))
+    }
+
+    if max_hourly_debits.is_some_and(|max| hourly_count + 1 > max as i64) {
+        return Ok(Some(SpendingRule::MaxHourlyDebits))
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use futures_util::future::join_all;
+    use uuid::Uuid;
+
+    use crate::config::Config;
+    use crate::db::Database;
+    use crate::errors::Error;
+    use crate::models::{SpendingRule, SpendingRules, Transaction};
+
+    const CUSTOMER: i32 = 3;
+    const CONCURRENT: usize = 8;
+
+    fn debit(amount: i64) -> Transaction {
+        Transaction{
+            id: Uuid::new_v4(),
+            customer_id: CUSTOMER as i64,
+            amount,
+            transaction_type: String::from("d"),
+            description: String::from("regra"),
+            category: None,
+            tags: vec![],
+            metadata: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn rules(max_daily_debit: Option<i64>, max_hourly_debits: Option<i32>) -> SpendingRules {
+        SpendingRules{
+            max_debit: None,
+            max_daily_debit,
+            max_hourly_debits,
+            blocked_patterns: vec![],
+        }
+    }
+
+    // debits already on the ledger, so the rules leave room for exactly one more
+    async fn debits_so_far(db: &Database) -> (i64, i64) {
+        let pg_client = db.pool.get().await.unwrap();
+        let row = pg_client.query_one(
+            "select \
+                coalesce(sum(amount) filter (where created_at >= \
+                    date_trunc('day', now() at time zone 'UTC') at time zone 'UTC'), 0)::bigint, \
+                count(*) filter (where created_at > now() - interval '1 hour') \
+            from transactions where customer_id = $1::int and transaction_type = 'd'",
+            &[&CUSTOMER],
+        ).await.unwrap();
+
+        (row.get(0), row.get(1))
+    }
+
+    // one success, every other debit rejected by `rule`
+    async fn race(db: &Database, amount: i64, rule: SpendingRule) {
+        let results = join_all((0..CONCURRENT).map(|_| db.create_transaction(debit(amount)))).await;
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results.iter().all(|result| match result {
+            Ok(_) => true,
+            Err(Error::RuleViolation(violated)) => *violated == rule,
+            Err(_) => false,
+        }));
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the postgres database configured by the DB_* variables"]
+    async fn concurrent_debits_respect_the_daily_and_hourly_rules() {
+        let config = envy::from_env::<Config>().unwrap();
+        let db = Database::init(&config).await.unwrap();
+
+        let (daily_total, _) = debits_so_far(&db).await;
+        db.save_spending_rules(CUSTOMER, &rules(Some(daily_total + 100), None)).await.unwrap();
+        race(&db, 60, SpendingRule::MaxDailyDebit).await;
+
+        let (_, hourly_count) = debits_so_far(&db).await;
+        db.save_spending_rules(CUSTOMER, &rules(None, Some(hourly_count as i32 + 1))).await.unwrap();
+        race(&db, 1, SpendingRule::MaxHourlyDebits).await;
+
+        db.save_spending_rules(CUSTOMER, &rules(None, None)).await.unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior};
+
+use crate::errors::Error;
+use crate::errors::Error::{Default, InsufficientLimit, NotFound};
+use crate::models::{FeePolicy, Transaction, TransactionCache};
+use crate::models::transaction::{Customer, CustomerLean};
+use crate::serializers::rinha_date_format;
+
+const LATEST_TRANSACTIONS: usize = 10;
+
+// customer and transactions from init-db.sql; jsonb and arrays become json
+// text, timestamps the rinha format so they sort as text
+const SCHEMA: &str = "
+create table if not exists customer (
+    id integer not null primary key,
+    credit_limit integer not null,
+    balance integer not null check (balance >= (-1 * credit_limit)),
+    latest_transactions text not null default '[]'
+);
+
+create table if not exists transactions (
+    seq integer primary key autoincrement,
+    id text not null unique,
+    customer_id integer not null references customer(id),
+    amount integer not null,
+    transaction_type text not null,
+    description text not null,
+    category text,
+    tags text not null default '[]',
+    metadata text,
+    created_at text not null,
+    balance integer
+);
+
+create index if not exists transactions_customer_created_idx on transactions (customer_id, created_at desc);
+create index if not exists transactions_customer_seq_idx on transactions (customer_id, seq);
+
+insert or ignore into customer (id, credit_limit, balance) values
+    (1, 100000, 0),
+    (2, 80000, 0),
+    (3, 1000000, 0),
+    (4, 10000000, 0),
+    (5, 500000, 0);
+";
+
+// Balances, last-10 caches and the ledger in a single SQLite file. One
+// connection serves every request from the blocking pool; writes take the
+// database lock up front so the limit check and the update are atomic.
+#[derive(Clone)]
+pub struct SqliteStore {
+    connection: Arc<Mutex<Connection>>,
+    fees: FeePolicy,
+}
+
+impl SqliteStore {
+    pub fn open(url: &str, fees: FeePolicy) -> rusqlite::Result<SqliteStore> {
+        let path = url.trim_start_matches("sqlite://");
+        let connection = if path == ":memory:" {
+            Connection::open_in_memory()?
+        } else {
+            Connection::open(path)?
+        };
+
+        connection.pragma_update(None, "journal_mode", "wal")?;
+        connection.pragma_update(None, "foreign_keys", "on")?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(SqliteStore{ connection: Arc::new(Mutex::new(connection)), fees })
+    }
+
+    pub async fn create_transaction(&self, transaction: Transaction) -> Result<CustomerLean, Error> {
+        let store = self.clone();
+
+        actix_web::rt::task::spawn_blocking(move || store.post(transaction))
+            .await
+            .unwrap_or(Err(Default))
+    }
+
+    pub async fn get_customer(&self, customer_id: i32) -> Result<Customer, Error> {
+        let store = self.clone();
+
+        actix_web::rt::task::spawn_blocking(move || store.customer(customer_id))
+            .await
+            .unwrap_or(Err(Default))
+    }
+
+    fn post(&self, transaction: Transaction) -> Result<CustomerLean, Error> {
+        let mut connection = self.connection.lock().unwrap();
+        let db_transaction = connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(fail)?;
+
+        let row = db_transaction.query_row(
+            "select credit_limit, balance, latest_transactions from customer where id = ?1",
+            [transaction.customer_id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?)),
+        ).optional().map_err(fail)?;
+
+        let Some((limit, mut balance, latest_transactions)) = row else {
+            return Err(NotFound)
+        };
+
+        let mut entries = vec![transaction];
+        if let Some(fee) = self.fees.debit_fee_for(&entries[0]) {
+            entries.push(fee);
+        }
+
+        let total: i64 = entries.iter().map(Transaction::signed_amount).sum();
+        if balance + total < -limit {
+            return Err(InsufficientLimit)
+        }
+
+        let mut latest: Vec<TransactionCache> = serde_json::from_str(&latest_transactions).unwrap_or_default();
+
+        for entry in &entries {
+            balance += entry.signed_amount();
+            latest.insert(0, TransactionCache::from_transaction(entry));
+
+            db_transaction.execute(
+                "insert into transactions (\
+                id, customer_id, amount, transaction_type, description, \
+                category, tags, metadata, created_at, balance\
+                ) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                rusqlite::params![
+                    entry.id.to_string(),
+                    entry.customer_id,
+                    entry.amount,
+                    entry.transaction_type,
+                    entry.description,
+                    entry.category,
+                    serde_json::to_string(&entry.tags).unwrap(),
+                    entry.metadata.as_ref().map(|metadata| metadata.to_string()),
+                    rinha_date_format::format(&entry.created_at),
+                    balance,
+                ],
+            ).map_err(fail)?;
+        }
+
+        latest.truncate(LATEST_TRANSACTIONS);
+
+        db_transaction.execute(
+            "update customer set balance = ?1, latest_transactions = ?2 where id = ?3",
+            rusqlite::params![balance, serde_json::to_string(&latest).unwrap(), entries[0].customer_id],
+        ).map_err(fail)?;
+
+        db_transaction.commit().map_err(fail)?;
+
+        Ok(CustomerLean{ limit, balance })
+    }
+
+    fn customer(&self, customer_id: i32) -> Result<Customer, Error> {
+        let connection = self.connection.lock().unwrap();
+
+        let row = connection.query_row(
+            "select credit_limit, balance, latest_transactions from customer where id = ?1",
+            [customer_id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?)),
+        ).optional().map_err(fail)?;
+
+        let Some((limit, balance, latest_transactions)) = row else {
+            return Err(NotFound)
+        };
+
+        Ok(Customer{
+            limit,
+            balance,
+            transactions: serde_json::from_str(&latest_transactions).unwrap_or_default(),
+        })
+    }
+}
+
+fn fail(err: rusqlite::Error) -> Error {
+    log::error!("sqlite: {}", err);
+    Default
+}
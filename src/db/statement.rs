@@ -0,0 +1,167 @@
+use tokio_postgres::IsolationLevel;
+use tokio_postgres::types::Type;
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::errors::Error::{Default, NotFound};
+use crate::models::{PeriodStatement, StatementPeriod, TransactionCache};
+use crate::responses::RawStatementResponse;
+
+impl Database {
+    pub async fn get_period_statement(
+        &self,
+        customer_id: i32,
+        period: StatementPeriod,
+    ) -> Result<PeriodStatement, Error> {
+        // only implemented on postgres, the other backends serve the last 10
+        if !self.is_postgres() {
+            return Err(Error::Invalid)
+        }
+
+        let mut pg_client = self.customer_client(customer_id).await?;
+
+        // both reads must see the same snapshot, or a concurrent write would
+        // make the balances disagree with the listed transactions
+        let db_transaction = pg_client.build_transaction()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .read_only(true)
+            .start()
+            .await
+            .map_err(|_| Default)?;
+
+        let starts_at = period.starts_at();
+        let ends_at = period.ends_at();
+
+        let totals = db_transaction.query_opt(
+            "select c.credit_limit, c.balance, \
+                coalesce(sum(case when t.transaction_type = 'c' then t.amount else -t.amount end), 0)::bigint as since_start, \
+                coalesce(sum(case when t.transaction_type = 'c' then t.amount else -t.amount end) \
+                    filter (where t.created_at >= $3::timestamptz), 0)::bigint as since_end, \
+                coalesce(sum(t.amount) \
+                    filter (where t.transaction_type = 'c' and t.created_at < $3::timestamptz), 0)::bigint as credits, \
+                coalesce(sum(t.amount) \
+                    filter (where t.transaction_type <> 'c' and t.created_at < $3::timestamptz), 0)::bigint as debits \
+            from customer c \
+            left join transactions t on t.customer_id = c.id and t.created_at >= $2::timestamptz \
+            where c.id = $1::int and c.moved_to is null \
+            group by c.id",
+            &[&customer_id, &starts_at, &ends_at],
+        ).await.map_err(|_| Default)?;
+
+        let Some(totals) = totals else {
+            let _ = db_transaction.rollback().await;
+            return Err(NotFound)
+        };
+
+        let rows = db_transaction.query(
+            "select id, amount, transaction_type, description, category, tags, metadata, created_at \
+            from transactions \
+            where customer_id = $1::int \
+                and created_at >= $2::timestamptz \
+                and created_at < $3::timestamptz \
+            order by created_at, seq",
+            &[&customer_id, &starts_at, &ends_at],
+        ).await.map_err(|_| Default)?;
+
+        db_transaction.commit().await.map_err(|_| Default)?;
+
+        let balance: i64 = totals.get("balance");
+        let since_start: i64 = totals.get("since_start");
+        let since_end: i64 = totals.get("since_end");
+
+        Ok(PeriodStatement{
+            period,
+            limit: totals.get("credit_limit"),
+            opening_balance: balance - since_start,
+            closing_balance: balance - since_end,
+            total_credits: totals.get("credits"),
+            total_debits: totals.get("debits"),
+            transactions: rows.into_iter().map(TransactionCache::from).collect(),
+        })
+    }
+
+    // The v1 statement body rendered from the latest_transactions text as
+    // postgres sends it, so the cached entries reach the client without a
+    // round trip through TransactionCache and its date parsing.
+    pub async fn get_statement_json(&self, customer_id: i32, after: Option<&str>) -> Result<String, Error> {
+        let pg_client = self.customer_read_client(customer_id, after).await?;
+
+        let statement = self.prepare(
+            &pg_client,
+            "select credit_limit, balance, latest_transactions::text \
+            from customer \
+            where id = $1 and moved_to is null",
+            &[Type::INT4],
+        ).await.map_err(|_| Default)?;
+
+        let row = pg_client.query_opt(&statement, &[&customer_id]).await
+            .map_err(|_| Default)?
+            .ok_or(NotFound)?;
+
+        let latest_transactions: Option<String> = row.get(2);
+
+        RawStatementResponse::render(
+            row.get(0),
+            row.get(1),
+            latest_transactions.as_deref().unwrap_or("[]"),
+        ).map_err(|_| Default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::config::Config;
+    use crate::db::Database;
+    use crate::models::Transaction;
+    use crate::responses::GetStatementResponse;
+
+    // data_extrato is taken at render time, so it can't match between two renders
+    fn without_date(body: &str) -> String {
+        let start = body.find("\"data_extrato\":\"").unwrap() + "\"data_extrato\":\"".len();
+        let end = start + body[start..].find('"').unwrap();
+
+        format!("{}{}", &body[..start], &body[end..])
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs the postgres database configured by the DB_* variables"]
+    async fn raw_statement_matches_serde() {
+        let config = envy::from_env::<Config>().unwrap();
+        let db = Database::init(&config).await.unwrap();
+
+        // keys out of jsonb's order, whitespace inside strings and a nested null
+        // are where the two renderings could drift apart
+        db.create_transaction(Transaction{
+            id: Uuid::new_v4(),
+            customer_id: 1,
+            amount: 1,
+            transaction_type: String::from("c"),
+            description: String::from("a \"b\" ç"),
+            category: Some(String::from("teste")),
+            tags: vec![String::from("x y"), String::from("z")],
+            metadata: Some(json!({"zz": {"b": [1, 2.5], "a": null}, "b": "c  d", "aa": true})),
+            created_at: Utc::now(),
+        }).await.unwrap();
+        db.create_transaction(Transaction{
+            id: Uuid::new_v4(),
+            customer_id: 1,
+            amount: 1,
+            transaction_type: String::from("c"),
+            description: String::from("simples"),
+            category: None,
+            tags: vec![],
+            metadata: None,
+            created_at: Utc::now(),
+        }).await.unwrap();
+
+        let raw = db.get_statement_json(1, None).await.unwrap();
+        let customer = db.get_customer_from_primary(1).await.unwrap();
+        let serde = serde_json::to_string(&GetStatementResponse::from_customer(&customer)).unwrap();
+
+        assert_eq!(without_date(&raw), without_date(&serde));
+    }
+}
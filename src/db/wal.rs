@@ -0,0 +1,263 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Transaction, TransactionCache};
+
+const MAX_BATCH: usize = 4096;
+const LATEST_TRANSACTIONS: usize = 10;
+
+pub type Ack = Box<dyn FnOnce() + Send>;
+
+struct WalItem {
+    entry: Option<Transaction>,
+    ack: Option<Ack>,
+}
+
+// A customer's balance and last entries, as replaying the log rebuilds them.
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Ledger {
+    pub balance: i64,
+    pub latest: VecDeque<TransactionCache>,
+}
+
+impl Ledger {
+    pub fn post(&mut self, transaction: &Transaction) {
+        self.balance += transaction.signed_amount();
+        self.latest.push_front(TransactionCache::from_transaction(transaction));
+        self.latest.truncate(LATEST_TRANSACTIONS);
+    }
+}
+
+// One line of the log: a posted entry, or, once the log has been compacted,
+// the whole ledger of a customer up to that point.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Record {
+    Snapshot{ customer_id: i32, snapshot: Ledger },
+    Entry(Transaction),
+}
+
+// Append-only log of every posted entry, one JSON transaction per line. A
+// single writer thread batches whatever is queued, fsyncs once and only then
+// runs the acks, so callers answer clients after their entry is durable.
+// Every `compact_entries` entries the writer replaces the log with one
+// snapshot line per customer, so it doesn't grow with the ledger.
+//
+// The log is only correct with a single writer: a lock next to it keeps a
+// second instance from opening it, and instances with logs of their own
+// would each hold different balances, so DB_BACKEND=memory runs one instance.
+#[derive(Clone)]
+pub struct Wal {
+    sender: Sender<WalItem>,
+}
+
+struct Compaction {
+    path: String,
+    every: u64,
+    pending: u64,
+    ledgers: HashMap<i32, Ledger>,
+}
+
+impl Wal {
+    // Replays the log into the ledgers it returns and opens it for appending.
+    // A torn last line left by a crash mid-write is cut off before new entries
+    // follow it, and a log with entries is compacted right away.
+    pub fn open(
+        path: &str,
+        sync_interval: Duration,
+        compact_entries: u64,
+    ) -> std::io::Result<(Wal, HashMap<i32, Ledger>)> {
+        let lock = lock(path)?;
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(path)?;
+
+        let mut reader = BufReader::new(&file);
+        let mut line = Vec::new();
+        let mut valid_len = 0u64;
+        let mut replayed = 0u64;
+        let mut ledgers: HashMap<i32, Ledger> = HashMap::new();
+
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 || line.last() != Some(&b'\n') {
+                break
+            }
+
+            match serde_json::from_slice::<Record>(&line) {
+                Ok(Record::Snapshot{ customer_id, snapshot }) => {
+                    ledgers.insert(customer_id, snapshot);
+                }
+                Ok(Record::Entry(transaction)) => {
+                    ledgers.entry(transaction.customer_id as i32).or_default().post(&transaction);
+                    replayed += 1;
+                }
+                Err(_) => break,
+            }
+
+            valid_len += read as u64;
+        }
+
+        if file.metadata()?.len() > valid_len {
+            log::warn!("truncating wal {} after {} valid entries", path, replayed);
+            file.set_len(valid_len)?;
+            file.sync_all()?;
+        }
+
+        log::info!("replayed {} wal entries from {}", replayed, path);
+
+        if replayed > 0 {
+            file = compact(path, &ledgers)?;
+        }
+
+        let compaction = Compaction{
+            path: path.to_string(),
+            every: compact_entries,
+            pending: 0,
+            ledgers: ledgers.clone(),
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name(String::from("nilapi-wal"))
+            .spawn(move || {
+                let _lock = lock;
+                write_loop(file, receiver, sync_interval, compaction)
+            })?;
+
+        Ok((Wal{ sender }, ledgers))
+    }
+
+    pub fn append(&self, transaction: &Transaction, ack: Option<Ack>) {
+        self.send(WalItem{ entry: Some(transaction.clone()), ack });
+    }
+
+    // runs `ack` once every entry appended before it is durable
+    pub fn barrier(&self, ack: Ack) {
+        self.send(WalItem{ entry: None, ack: Some(ack) });
+    }
+
+    fn send(&self, item: WalItem) {
+        if self.sender.send(item).is_err() {
+            log::error!("wal writer is gone");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn write_loop(file: File, receiver: Receiver<WalItem>, sync_interval: Duration, mut compaction: Compaction) {
+    let mut writer = BufWriter::new(file);
+
+    while let Ok(first) = receiver.recv() {
+        let mut batch = vec![first];
+        collect(&receiver, &mut batch, sync_interval);
+
+        let mut dirty = false;
+        for item in &batch {
+            if let Some(entry) = &item.entry {
+                let mut record = serde_json::to_vec(entry).unwrap();
+                record.push(b'\n');
+
+                if let Err(err) = writer.write_all(&record) {
+                    fail(err);
+                }
+
+                compaction.ledgers.entry(entry.customer_id as i32).or_default().post(entry);
+                compaction.pending += 1;
+                dirty = true;
+            }
+        }
+
+        if dirty {
+            if let Err(err) = writer.flush().and_then(|_| writer.get_ref().sync_data()) {
+                fail(err);
+            }
+        }
+
+        for item in batch {
+            if let Some(ack) = item.ack {
+                ack();
+            }
+        }
+
+        if compaction.every > 0 && compaction.pending >= compaction.every {
+            match compact(&compaction.path, &compaction.ledgers) {
+                Ok(file) => writer = BufWriter::new(file),
+                // the old log is still complete, appends carry on there
+                Err(err) => log::warn!("wal compaction failed: {}", err),
+            }
+            compaction.pending = 0;
+        }
+    }
+}
+
+// Writes the ledgers next to the log and renames them over it. Until the
+// rename the old log stays whole, after it the snapshot alone is the log;
+// either way a crash replays to the same balances.
+fn compact(path: &str, ledgers: &HashMap<i32, Ledger>) -> std::io::Result<File> {
+    let compacted = format!("{}.compact", path);
+    let mut writer = BufWriter::new(File::create(&compacted)?);
+
+    for (customer_id, ledger) in ledgers {
+        let record = Record::Snapshot{ customer_id: *customer_id, snapshot: ledger.clone() };
+        serde_json::to_writer(&mut writer, &record)?;
+        writer.write_all(b"\n")?;
+    }
+
+    writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+    fs::rename(&compacted, path)?;
+
+    let directory = Path::new(path).parent().filter(|parent| !parent.as_os_str().is_empty());
+    File::open(directory.unwrap_or(Path::new(".")))?.sync_all()?;
+
+    log::info!("compacted wal {} to {} customers", path, ledgers.len());
+
+    OpenOptions::new().read(true).append(true).open(path)
+}
+
+// held by the writer thread for the life of the process
+fn lock(path: &str) -> std::io::Result<File> {
+    let lock_path = format!("{}.lock", path);
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)?;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        return Err(Error::new(
+            ErrorKind::AddrInUse,
+            format!("{} is held by another instance, the memory backend runs a single instance", lock_path),
+        ))
+    }
+
+    Ok(file)
+}
+
+// Takes everything already queued, then keeps waiting up to the sync
+// interval so bursts share one fsync.
+fn collect(receiver: &Receiver<WalItem>, batch: &mut Vec<WalItem>, sync_interval: Duration) {
+    let deadline = Instant::now() + sync_interval;
+
+    while batch.len() < MAX_BATCH {
+        let item = match receiver.try_recv() {
+            Ok(item) => item,
+            Err(_) => match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(item) => item,
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return,
+            },
+        };
+
+        batch.push(item);
+    }
+}
+
+// balances in memory are already ahead of the log, recovery has to rebuild
+// them from what did reach the disk
+fn fail(err: std::io::Error) -> ! {
+    log::error!("wal write failed, exiting: {}", err);
+    std::process::exit(1);
+}
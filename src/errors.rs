@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod errors;
 
 pub use errors::Error;
\ No newline at end of file
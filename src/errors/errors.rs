@@ -1,5 +1,33 @@
+use crate::models::SpendingRule;
+
 #[derive(Debug)]
 pub enum Error {
     NotFound,
-    Default
-}
\ No newline at end of file
+    Default,
+    InsufficientLimit,
+    Invalid,
+    RuleViolation(SpendingRule),
+}
+
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::NotFound => "cliente_nao_encontrado",
+            Error::Default => "erro_interno",
+            Error::InsufficientLimit => "limite_insuficiente",
+            Error::Invalid => "requisicao_invalida",
+            Error::RuleViolation(rule) => rule.code(),
+        }
+    }
+
+    // v2 speaks English; v1 keeps the Portuguese codes it has always returned
+    pub fn code_v2(&self) -> &'static str {
+        match self {
+            Error::NotFound => "customer_not_found",
+            Error::Default => "internal_error",
+            Error::InsufficientLimit => "insufficient_limit",
+            Error::Invalid => "invalid_request",
+            Error::RuleViolation(rule) => rule.code_v2(),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+mod csv;
+mod ofx;
+mod text;
+
+use actix_web::{HttpMessage, HttpRequest};
+use actix_web::http::header::Accept;
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::models::{PeriodStatement, TransactionCache};
+use crate::models::transaction::Customer;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StatementFormat {
+    Json,
+    Csv,
+    Ofx,
+    Text,
+}
+
+impl StatementFormat {
+    // picks the highest ranked media type we know how to render, JSON otherwise
+    pub fn negotiate(req: &HttpRequest) -> StatementFormat {
+        let Some(accept) = req.get_header::<Accept>() else {
+            return StatementFormat::Json
+        };
+
+        for mime in accept.ranked() {
+            match mime.essence_str() {
+                "text/csv" => return StatementFormat::Csv,
+                "application/x-ofx" => return StatementFormat::Ofx,
+                "text/plain" => return StatementFormat::Text,
+                "application/json" | "application/*" | "*/*" => return StatementFormat::Json,
+                _ => {}
+            }
+        }
+
+        StatementFormat::Json
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            StatementFormat::Json => "application/json",
+            StatementFormat::Csv => "text/csv; charset=utf-8",
+            StatementFormat::Ofx => "application/x-ofx",
+            StatementFormat::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+pub struct StatementExport<'a> {
+    pub customer_id: i32,
+    pub date: DateTime<Utc>,
+    pub limit: i64,
+    pub period: Option<(NaiveDate, NaiveDate)>,
+    pub opening_balance: Option<i64>,
+    pub closing_balance: i64,
+    pub totals: Option<(i64, i64)>,
+    pub transactions: &'a [TransactionCache],
+}
+
+impl<'a> StatementExport<'a> {
+    pub fn from_customer(customer_id: i32, customer: &'a Customer) -> StatementExport<'a> {
+        StatementExport{
+            customer_id,
+            date: Utc::now(),
+            limit: customer.limit,
+            period: None,
+            opening_balance: None,
+            closing_balance: customer.balance,
+            totals: None,
+            transactions: &customer.transactions,
+        }
+    }
+
+    pub fn from_period(customer_id: i32, statement: &'a PeriodStatement) -> StatementExport<'a> {
+        StatementExport{
+            customer_id,
+            date: Utc::now(),
+            limit: statement.limit,
+            period: Some((statement.period.start, statement.period.end)),
+            opening_balance: Some(statement.opening_balance),
+            closing_balance: statement.closing_balance,
+            totals: Some((statement.total_credits, statement.total_debits)),
+            transactions: &statement.transactions,
+        }
+    }
+
+    pub fn render(&self, format: StatementFormat) -> String {
+        match format {
+            StatementFormat::Csv => csv::render(self),
+            StatementFormat::Ofx => ofx::render(self),
+            StatementFormat::Text => text::render(self),
+            StatementFormat::Json => unreachable!("json statements are serialized by serde"),
+        }
+    }
+}
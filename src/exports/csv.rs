@@ -0,0 +1,128 @@
+use crate::exports::StatementExport;
+use crate::serializers::rinha_date_format;
+
+// columns are part of the contract, append new ones at the end only
+const HEADER: &str = "realizada_em,tipo,valor,descricao,categoria,tags";
+
+pub fn render(statement: &StatementExport) -> String {
+    let mut out = String::from(HEADER);
+    out.push_str("\r\n");
+
+    for transaction in statement.transactions {
+        let row = [
+            rinha_date_format::format(&transaction.created_at),
+            transaction.transaction_type.clone(),
+            transaction.amount.to_string(),
+            escape(&transaction.description),
+            escape(transaction.category.as_deref().unwrap_or_default()),
+            escape(&join_tags(&transaction.tags)),
+        ];
+
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+
+    out
+}
+
+// Tags share one cell separated by `;`; a `;` or `\` inside a tag is
+// escaped with `\` so the list splits back into the same tags.
+fn join_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| tag.replace('\\', "\\\\").replace(';', "\\;"))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+// Spreadsheets evaluate a cell starting with one of these as a formula, so
+// such text is prefixed with a quote and shown as typed.
+fn escape(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\r', '\n']) {
+        return format!("\"{}\"", value.replace('"', "\"\""))
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::exports::StatementExport;
+    use crate::models::TransactionCache;
+    use super::*;
+
+    fn transaction(description: &str, category: Option<&str>, tags: &[&str]) -> TransactionCache {
+        TransactionCache{
+            id: None,
+            amount: 1050,
+            transaction_type: String::from("d"),
+            description: description.to_string(),
+            category: category.map(str::to_string),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            metadata: None,
+            created_at: Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap(),
+        }
+    }
+
+    fn export(transactions: &[TransactionCache]) -> StatementExport<'_> {
+        StatementExport{
+            customer_id: 1,
+            date: Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap(),
+            limit: 100000,
+            period: None,
+            opening_balance: None,
+            closing_balance: -1050,
+            totals: None,
+            transactions,
+        }
+    }
+
+    #[test]
+    fn formula_cells_are_prefixed_with_a_quote() {
+        assert_eq!(escape("=1+1"), "'=1+1");
+        assert_eq!(escape("+55"), "'+55");
+        assert_eq!(escape("-2"), "'-2");
+        assert_eq!(escape("@sum"), "'@sum");
+        assert_eq!(escape("\tx"), "'\tx");
+        assert_eq!(escape("\rx"), "\"'\rx\"");
+        assert_eq!(escape("a=b"), "a=b");
+    }
+
+    #[test]
+    fn separators_and_quotes_are_quoted() {
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape("a\nb"), "\"a\nb\"");
+        assert_eq!(escape("=a,b"), "\"'=a,b\"");
+        assert_eq!(escape("plain"), "plain");
+    }
+
+    #[test]
+    fn tags_keep_their_separator_escaped() {
+        assert_eq!(join_tags(&[]), "");
+        assert_eq!(join_tags(&[String::from("a"), String::from("b")]), "a;b");
+        assert_eq!(join_tags(&[String::from("a;b"), String::from("c\\")]), "a\\;b;c\\\\");
+    }
+
+    #[test]
+    fn render_writes_a_header_and_one_row_per_transaction() {
+        let transactions = [
+            transaction("pix", None, &[]),
+            transaction("=cmd", Some("casa, lar"), &["x;y", "z"]),
+        ];
+
+        assert_eq!(
+            render(&export(&transactions)),
+            "realizada_em,tipo,valor,descricao,categoria,tags\r\n\
+            2026-03-01T12:00:00.000000Z,d,1050,pix,,\r\n\
+            2026-03-01T12:00:00.000000Z,d,1050,'=cmd,\"casa, lar\",x\\;y;z\r\n",
+        );
+    }
+}
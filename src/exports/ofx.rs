@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+
+use crate::exports::StatementExport;
+
+// OFX mandates its own datetime layout, so rinha_date_format cannot be used here
+const OFX_DATE_FORMAT: &str = "%Y%m%d%H%M%S%.3f[0:GMT]";
+
+pub fn render(statement: &StatementExport) -> String {
+    let (start, end) = match statement.period {
+        Some((start, end)) => (
+            start.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+            end.and_hms_opt(23, 59, 59).unwrap().and_utc(),
+        ),
+        None => {
+            let oldest = statement.transactions.iter().map(|t| t.created_at).min();
+            (oldest.unwrap_or(statement.date), statement.date)
+        }
+    };
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+    out.push_str("<?OFX OFXHEADER=\"200\" VERSION=\"220\" SECURITY=\"NONE\" OLDFILEUID=\"NONE\" NEWFILEUID=\"NONE\"?>\n");
+    out.push_str("<OFX>\n<SIGNONMSGSRSV1>\n<SONRS>\n");
+    out.push_str("<STATUS>\n<CODE>0</CODE>\n<SEVERITY>INFO</SEVERITY>\n</STATUS>\n");
+    out.push_str(&format!("<DTSERVER>{}</DTSERVER>\n<LANGUAGE>POR</LANGUAGE>\n", date(&statement.date)));
+    out.push_str("</SONRS>\n</SIGNONMSGSRSV1>\n");
+    out.push_str("<BANKMSGSRSV1>\n<STMTTRNRS>\n<TRNUID>0</TRNUID>\n");
+    out.push_str("<STATUS>\n<CODE>0</CODE>\n<SEVERITY>INFO</SEVERITY>\n</STATUS>\n");
+    out.push_str("<STMTRS>\n<CURDEF>BRL</CURDEF>\n");
+    out.push_str(&format!(
+        "<BANKACCTFROM>\n<BANKID>NILAPI</BANKID>\n<ACCTID>{}</ACCTID>\n<ACCTTYPE>CHECKING</ACCTTYPE>\n</BANKACCTFROM>\n",
+        statement.customer_id,
+    ));
+    out.push_str(&format!(
+        "<BANKTRANLIST>\n<DTSTART>{}</DTSTART>\n<DTEND>{}</DTEND>\n",
+        date(&start),
+        date(&end),
+    ));
+
+    for (index, transaction) in statement.transactions.iter().enumerate() {
+        let (kind, amount) = match transaction.transaction_type.as_str() {
+            "c" => ("CREDIT", transaction.amount),
+            "j" => ("INT", -transaction.amount),
+            "t" => ("FEE", -transaction.amount),
+            _ => ("DEBIT", -transaction.amount),
+        };
+
+        out.push_str("<STMTTRN>\n");
+        out.push_str(&format!("<TRNTYPE>{}</TRNTYPE>\n", kind));
+        out.push_str(&format!("<DTPOSTED>{}</DTPOSTED>\n", date(&transaction.created_at)));
+        out.push_str(&format!("<TRNAMT>{}</TRNAMT>\n", money(amount)));
+        out.push_str(&format!("<FITID>{}</FITID>\n", fitid(statement, index)));
+        out.push_str(&format!("<MEMO>{}</MEMO>\n", escape(&transaction.description)));
+        out.push_str("</STMTTRN>\n");
+    }
+
+    out.push_str("</BANKTRANLIST>\n");
+    out.push_str(&format!(
+        "<LEDGERBAL>\n<BALAMT>{}</BALAMT>\n<DTASOF>{}</DTASOF>\n</LEDGERBAL>\n",
+        money(statement.closing_balance),
+        date(&end.min(statement.date)),
+    ));
+    out.push_str("</STMTRS>\n</STMTTRNRS>\n</BANKMSGSRSV1>\n</OFX>\n");
+
+    out
+}
+
+// Importers dedupe on FITID, so it is the ledger id. Entries cached before
+// ids were kept fall back to their position, as debits, their fees and batch
+// items share a timestamp.
+fn fitid(statement: &StatementExport, index: usize) -> String {
+    let transaction = &statement.transactions[index];
+
+    match transaction.id {
+        Some(id) => id.simple().to_string(),
+        None => format!("{}-{}-{}", statement.customer_id, transaction.created_at.timestamp_micros(), index),
+    }
+}
+
+fn date(value: &DateTime<Utc>) -> String {
+    format!("{}", value.format(OFX_DATE_FORMAT))
+}
+
+// amounts are stored in cents
+fn money(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use uuid::Uuid;
+
+    use crate::exports::StatementExport;
+    use crate::models::TransactionCache;
+    use super::*;
+
+    fn transaction(id: Option<Uuid>, transaction_type: &str, amount: i64, description: &str) -> TransactionCache {
+        TransactionCache{
+            id,
+            amount,
+            transaction_type: transaction_type.to_string(),
+            description: description.to_string(),
+            category: None,
+            tags: vec![],
+            metadata: None,
+            created_at: Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn amounts_are_signed_and_in_units() {
+        assert_eq!(money(1050), "10.50");
+        assert_eq!(money(-5), "-0.05");
+        assert_eq!(money(0), "0.00");
+        assert_eq!(money(-100000), "-1000.00");
+    }
+
+    #[test]
+    fn memo_is_escaped() {
+        assert_eq!(escape("a<b>&c"), "a&lt;b&gt;&amp;c");
+    }
+
+    #[test]
+    fn render_writes_signon_transactions_and_balance() {
+        let id = Uuid::parse_str("0190f1a2-3b4c-7d5e-8f60-718293a4b5c6").unwrap();
+        let transactions = [
+            transaction(Some(id), "c", 1050, "pix <in>"),
+            transaction(None, "d", 200, "boleto"),
+            transaction(None, "t", 5, "tarifa"),
+        ];
+        let statement = StatementExport{
+            customer_id: 7,
+            date: Utc.with_ymd_and_hms(2026, 3, 5, 8, 0, 0).unwrap(),
+            limit: 100000,
+            period: Some((NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2026, 3, 31).unwrap())),
+            opening_balance: Some(0),
+            closing_balance: 845,
+            totals: Some((1050, 205)),
+            transactions: &transactions,
+        };
+
+        let ofx = render(&statement);
+
+        assert!(ofx.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n<?OFX OFXHEADER=\"200\""));
+        assert!(ofx.contains("<SONRS>\n<STATUS>\n<CODE>0</CODE>\n<SEVERITY>INFO</SEVERITY>\n</STATUS>\n\
+            <DTSERVER>20260305080000.000[0:GMT]</DTSERVER>\n<LANGUAGE>POR</LANGUAGE>\n</SONRS>"));
+        assert!(ofx.find("<SIGNONMSGSRSV1>").unwrap() < ofx.find("<BANKMSGSRSV1>").unwrap());
+        assert!(ofx.contains("<ACCTID>7</ACCTID>"));
+        assert!(ofx.contains("<DTSTART>20260301000000.000[0:GMT]</DTSTART>\n<DTEND>20260331235959.000[0:GMT]</DTEND>"));
+        assert!(ofx.contains("<STMTTRN>\n<TRNTYPE>CREDIT</TRNTYPE>\n<DTPOSTED>20260301120000.000[0:GMT]</DTPOSTED>\n\
+            <TRNAMT>10.50</TRNAMT>\n<FITID>0190f1a23b4c7d5e8f60718293a4b5c6</FITID>\n<MEMO>pix &lt;in&gt;</MEMO>\n</STMTTRN>"));
+        assert!(ofx.contains("<TRNTYPE>DEBIT</TRNTYPE>\n<DTPOSTED>20260301120000.000[0:GMT]</DTPOSTED>\n<TRNAMT>-2.00</TRNAMT>\n\
+            <FITID>7-1772366400000000-1</FITID>"));
+        assert!(ofx.contains("<TRNTYPE>FEE</TRNTYPE>\n<DTPOSTED>20260301120000.000[0:GMT]</DTPOSTED>\n<TRNAMT>-0.05</TRNAMT>\n\
+            <FITID>7-1772366400000000-2</FITID>"));
+        // the balance is as of the statement date when the period is still open
+        assert!(ofx.contains("<LEDGERBAL>\n<BALAMT>8.45</BALAMT>\n<DTASOF>20260305080000.000[0:GMT]</DTASOF>\n</LEDGERBAL>"));
+        assert!(ofx.ends_with("</STMTRS>\n</STMTTRNRS>\n</BANKMSGSRSV1>\n</OFX>\n"));
+    }
+
+    #[test]
+    fn render_without_period_spans_the_listed_transactions() {
+        let transactions = [transaction(None, "c", 100, "pix")];
+        let statement = StatementExport{
+            customer_id: 1,
+            date: Utc.with_ymd_and_hms(2026, 3, 5, 8, 0, 0).unwrap(),
+            limit: 100000,
+            period: None,
+            opening_balance: None,
+            closing_balance: 100,
+            totals: None,
+            transactions: &transactions,
+        };
+
+        let ofx = render(&statement);
+
+        assert!(ofx.contains("<DTSTART>20260301120000.000[0:GMT]</DTSTART>\n<DTEND>20260305080000.000[0:GMT]</DTEND>"));
+    }
+}
@@ -0,0 +1,49 @@
+use crate::exports::StatementExport;
+use crate::serializers::rinha_date_format;
+
+const WIDTH: usize = 72;
+
+pub fn render(statement: &StatementExport) -> String {
+    let rule = "-".repeat(WIDTH);
+    let mut out = String::new();
+
+    out.push_str(&format!("EXTRATO CLIENTE {}\n", statement.customer_id));
+    out.push_str(&format!("{:<16}{}\n", "DATA EXTRATO", rinha_date_format::format(&statement.date)));
+    if let Some((start, end)) = statement.period {
+        out.push_str(&format!("{:<16}{} A {}\n", "PERIODO", start, end));
+    }
+    out.push_str(&format!("{:<16}{:>14}\n", "LIMITE", statement.limit));
+    if let Some(opening_balance) = statement.opening_balance {
+        out.push_str(&format!("{:<16}{:>14}\n", "SALDO INICIAL", opening_balance));
+    }
+
+    out.push_str(&rule);
+    out.push('\n');
+    out.push_str(&format!("{:<29}{:<5}{:>14}  {:<10}  {}\n", "REALIZADA EM", "TIPO", "VALOR", "DESCRICAO", "CATEGORIA"));
+    out.push_str(&rule);
+    out.push('\n');
+
+    for transaction in statement.transactions {
+        let line = format!(
+            "{:<29}{:<5}{:>14}  {:<10}  {}",
+            rinha_date_format::format(&transaction.created_at),
+            transaction.transaction_type.to_uppercase(),
+            transaction.amount,
+            transaction.description,
+            transaction.category.as_deref().unwrap_or_default(),
+        );
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out.push_str(&rule);
+    out.push('\n');
+    if let Some((credits, debits)) = statement.totals {
+        out.push_str(&format!("{:<16}{:>14}\n", "CREDITOS", credits));
+        out.push_str(&format!("{:<16}{:>14}\n", "DEBITOS", debits));
+    }
+    let balance_label = if statement.period.is_some() { "SALDO FINAL" } else { "SALDO" };
+    out.push_str(&format!("{:<16}{:>14}\n", balance_label, statement.closing_balance));
+
+    out
+}
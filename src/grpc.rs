@@ -0,0 +1,50 @@
+mod service;
+
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("nilapi.v1");
+}
+
+use std::net::SocketAddr;
+
+use tonic::transport::Server;
+
+use crate::config::Config;
+use crate::db::Database;
+use proto::nilapi_server::NilapiServer;
+use service::NilapiService;
+
+// gRPC is opt-in: it only listens when GRPC_URL is set
+pub fn spawn(db: Database, config: &Config) {
+    let Some(grpc_url) = &config.grpc_url else {
+        return
+    };
+
+    let addr: SocketAddr = grpc_url.parse().unwrap_or_else(|err|
+        panic!("{}", format!("invalid grpc url - {:?}", err))
+    );
+
+    // tonic gets a multi-threaded runtime of its own instead of sharing the
+    // single-threaded one actix runs main on
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .thread_name("grpc")
+        .enable_all()
+        .build()
+        .unwrap_or_else(|err| panic!("{}", format!("fail to start grpc runtime - {:?}", err)));
+
+    std::thread::Builder::new()
+        .name(String::from("grpc"))
+        .spawn(move || runtime.block_on(async move {
+            let result = Server::builder()
+                .add_service(NilapiServer::new(NilapiService::new(db)))
+                .serve(addr)
+                .await;
+
+            if let Err(err) = result {
+                log::error!("grpc server stopped: {}", err);
+            }
+        }))
+        .unwrap_or_else(|err| panic!("{}", format!("fail to start grpc thread - {:?}", err)));
+
+    println!("grpc listening on {}", addr);
+}
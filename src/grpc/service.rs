@@ -0,0 +1,142 @@
+use std::pin::Pin;
+
+use futures_util::{Stream, TryStreamExt};
+use tonic::{Request, Response, Status};
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::handlers::submit_transaction;
+use crate::models::TransactionCache;
+use crate::models::transaction::TransactionFilter;
+use crate::requests::TransactionPayload;
+use crate::serializers::rinha_date_format;
+use super::proto::nilapi_server::Nilapi;
+use super::proto::{
+    CreateTransactionReply, CreateTransactionRequest, GetStatementRequest,
+    ListTransactionsRequest, Statement, TransactionEntry,
+};
+
+const DEFAULT_LIST_LIMIT: i64 = 50;
+const MAX_LIST_LIMIT: i64 = 1000;
+
+type TransactionStream = Pin<Box<dyn Stream<Item = Result<TransactionEntry, Status>> + Send>>;
+
+pub struct NilapiService {
+    db: Database,
+}
+
+impl NilapiService {
+    pub fn new(db: Database) -> NilapiService {
+        NilapiService{ db }
+    }
+}
+
+#[tonic::async_trait]
+impl Nilapi for NilapiService {
+    async fn create_transaction(
+        &self,
+        request: Request<CreateTransactionRequest>,
+    ) -> Result<Response<CreateTransactionReply>, Status> {
+        let request = request.into_inner();
+
+        let mut transaction_type = request.r#type.chars();
+        let (Some(type_char), None) = (transaction_type.next(), transaction_type.next()) else {
+            return Err(to_status(Error::Invalid))
+        };
+
+        let payload = TransactionPayload{
+            amount: request.amount,
+            transaction_type: type_char,
+            description: request.description,
+            category: request.category,
+            tags: request.tags,
+            metadata: None,
+        };
+
+        let customer = submit_transaction(&self.db, request.customer_id, &payload)
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(CreateTransactionReply{
+            limit: customer.limit,
+            balance: customer.balance,
+        }))
+    }
+
+    async fn get_statement(
+        &self,
+        request: Request<GetStatementRequest>,
+    ) -> Result<Response<Statement>, Status> {
+        let customer_id = request.into_inner().customer_id;
+
+        if customer_id < 0 {
+            return Err(to_status(Error::NotFound))
+        }
+
+        let customer = self.db.get_customer_by_id(customer_id, None)
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(Statement{
+            balance: customer.balance,
+            limit: customer.limit,
+            date: rinha_date_format::format(&chrono::Utc::now()),
+            latest_transactions: customer.transactions.iter().map(to_entry).collect(),
+        }))
+    }
+
+    type ListTransactionsStream = TransactionStream;
+
+    async fn list_transactions(
+        &self,
+        request: Request<ListTransactionsRequest>,
+    ) -> Result<Response<Self::ListTransactionsStream>, Status> {
+        let request = request.into_inner();
+
+        if request.customer_id < 0 {
+            return Err(to_status(Error::NotFound))
+        }
+
+        let limit = match request.limit {
+            0 => DEFAULT_LIST_LIMIT,
+            limit => limit.clamp(1, MAX_LIST_LIMIT),
+        };
+
+        let filter = TransactionFilter{
+            category: request.category,
+            tag: request.tag,
+            limit,
+            ..TransactionFilter::default()
+        };
+
+        let transactions = self.db.stream_transactions(request.customer_id, &filter, None)
+            .await
+            .map_err(to_status)?;
+
+        let entries = transactions
+            .map_ok(|transaction| to_entry(&transaction))
+            .map_err(to_status);
+
+        Ok(Response::new(Box::pin(entries)))
+    }
+}
+
+fn to_entry(transaction: &TransactionCache) -> TransactionEntry {
+    TransactionEntry{
+        amount: transaction.amount,
+        r#type: transaction.transaction_type.clone(),
+        description: transaction.description.clone(),
+        created_at: rinha_date_format::format(&transaction.created_at),
+        category: transaction.category.clone(),
+        tags: transaction.tags.clone(),
+    }
+}
+
+fn to_status(err: Error) -> Status {
+    match err {
+        Error::NotFound => Status::not_found(err.code()),
+        Error::Invalid => Status::invalid_argument(err.code()),
+        Error::InsufficientLimit | Error::RuleViolation(_) => Status::failed_precondition(err.code()),
+        Error::Default => Status::internal(err.code()),
+    }
+}
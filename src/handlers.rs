@@ -0,0 +1,29 @@
+pub mod schedule;
+pub mod history;
+pub mod spending_rule;
+pub mod fee;
+pub mod webhook;
+mod admin;
+pub mod event;
+mod transaction;
+mod socket;
+pub mod batch;
+mod docs;
+pub mod v2;
+mod consistency;
+pub mod ready;
+
+pub use schedule::create_schedule;
+pub use history::get_history;
+pub use spending_rule::{get_spending_rules, save_spending_rules};
+pub use fee::simulate_fees;
+pub use webhook::{save_webhook, delete_webhook};
+pub use admin::{AdminToken, get_dead_letters, replay_dead_letters, create_api_key, get_cache_stats};
+pub use event::stream_events;
+pub use transaction::submit_transaction;
+pub use socket::transactions_socket;
+pub use batch::create_transactions_batch;
+pub use docs::{get_openapi, get_docs};
+pub use consistency::{read_after, append_lsn};
+pub use ready::get_ready;
+pub use v2::{create_transaction_v2, get_statement_v2, get_history_v2, reject_request};
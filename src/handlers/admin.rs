@@ -0,0 +1,126 @@
+use actix_web::{get, post, HttpRequest, HttpResponse, web::Path, web::Query};
+use actix_web::web::Data;
+use subtle::ConstantTimeEq;
+use validator::{Validate};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::errors::Error;
+use crate::models::CustomerURL;
+use crate::requests::ReplayQuery;
+use crate::responses::{ApiKeyResponse, CacheStatsResponse, DeadLetterResponse, ReplayResponse};
+
+const DEAD_LETTER_PAGE: i64 = 100;
+
+#[derive(Clone)]
+pub struct AdminToken(Option<String>);
+
+impl AdminToken {
+    pub fn from_config(config: &Config) -> AdminToken {
+        AdminToken(config.admin_token.clone())
+    }
+
+    // admin routes stay closed unless ADMIN_TOKEN is configured
+    pub fn authorize(&self, req: &HttpRequest) -> bool {
+        let Some(token) = &self.0 else {
+            return false
+        };
+
+        bearer_token(req).is_some_and(|value| value.as_bytes().ct_eq(token.as_bytes()).into())
+    }
+}
+
+pub fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+// the customer's own API key, or the admin token
+pub async fn authorize_customer(req: &HttpRequest, admin_token: &AdminToken, db: &Database, customer_id: i32) -> bool {
+    if admin_token.authorize(req) {
+        return true
+    }
+
+    match bearer_token(req) {
+        Some(token) => db.get_customer_by_api_key(token).await == Some(customer_id),
+        None => false,
+    }
+}
+
+#[get("/admin/webhooks/falhas")]
+pub async fn get_dead_letters(
+    req: HttpRequest,
+    admin_token: Data<AdminToken>,
+    db: Data<Database>,
+) -> HttpResponse {
+    if !admin_token.authorize(&req) {
+        return HttpResponse::Unauthorized().into()
+    }
+
+    let Ok(dead_letters) = db.get_dead_letters(DEAD_LETTER_PAGE).await else {
+        return HttpResponse::InternalServerError().into()
+    };
+
+    let dead_letters: Vec<DeadLetterResponse> = dead_letters
+        .iter()
+        .map(DeadLetterResponse::from_model)
+        .collect();
+
+    HttpResponse::Ok().json(dead_letters)
+}
+
+#[post("/admin/webhooks/falhas/reprocessar")]
+pub async fn replay_dead_letters(
+    req: HttpRequest,
+    query: Query<ReplayQuery>,
+    admin_token: Data<AdminToken>,
+    db: Data<Database>,
+) -> HttpResponse {
+    if !admin_token.authorize(&req) {
+        return HttpResponse::Unauthorized().into()
+    }
+
+    let is_valid = query.validate();
+    if is_valid.is_err() {
+        return HttpResponse::UnprocessableEntity().into()
+    }
+
+    let Ok(replayed) = db.replay_dead_letters(query.shard, query.id).await else {
+        return HttpResponse::InternalServerError().into()
+    };
+
+    HttpResponse::Ok().json(ReplayResponse{ replayed })
+}
+
+#[post("/admin/clientes/{customer_id}/chaves")]
+pub async fn create_api_key(
+    req: HttpRequest,
+    customer_url: Path<CustomerURL>,
+    admin_token: Data<AdminToken>,
+    db: Data<Database>,
+) -> HttpResponse {
+    if !admin_token.authorize(&req) {
+        return HttpResponse::Unauthorized().into()
+    }
+
+    match db.create_api_key(customer_url.customer_id).await {
+        Ok(token) => HttpResponse::Created().json(ApiKeyResponse{ token }),
+        Err(Error::NotFound) => HttpResponse::NotFound().into(),
+        Err(_) => HttpResponse::InternalServerError().into(),
+    }
+}
+
+#[get("/admin/cache/extrato")]
+pub async fn get_cache_stats(
+    req: HttpRequest,
+    admin_token: Data<AdminToken>,
+    db: Data<Database>,
+) -> HttpResponse {
+    if !admin_token.authorize(&req) {
+        return HttpResponse::Unauthorized().into()
+    }
+
+    HttpResponse::Ok().json(CacheStatsResponse::from_stats(&db.statements.stats()))
+}
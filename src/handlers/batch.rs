@@ -0,0 +1,91 @@
+use actix_web::{post, HttpResponse, web::Json, web::Path, web::Query};
+use actix_web::web::Data;
+use chrono::Utc;
+use validator::{Validate};
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::models::CustomerURL;
+use crate::requests::{BatchMode, BatchQuery, TransactionPayload};
+use crate::responses::{BatchFailureResponse, BatchResponse};
+use super::append_lsn;
+
+const MAX_BATCH_SIZE: usize = 1000;
+
+#[utoipa::path(
+    tag = "transacoes",
+    params(("customer_id" = i32, Path, description = "customer id"), BatchQuery),
+    request_body = Vec<TransactionPayload>,
+    responses(
+        (status = 200, body = BatchResponse),
+        (status = 404, description = "unknown customer"),
+        (status = 422, description = "invalid batch, or the item that aborted an atomic batch", body = BatchFailureResponse),
+        (status = 500, description = "database failure"),
+    ),
+)]
+#[post("/clientes/{customer_id}/transacoes/lote")]
+pub async fn create_transactions_batch(
+    customer_url: Path<CustomerURL>,
+    query: Query<BatchQuery>,
+    payload: Json<Vec<TransactionPayload>>,
+    db: Data<Database>,
+) -> HttpResponse {
+    if payload.is_empty() || payload.len() > MAX_BATCH_SIZE {
+        return HttpResponse::UnprocessableEntity().into()
+    }
+
+    let customer_id = customer_url.customer_id;
+
+    if customer_id < 0 {
+        return HttpResponse::NotFound().into()
+    }
+
+    let mode = query.mode;
+    let time_now = Utc::now();
+
+    let items: Vec<_> = payload
+        .iter()
+        .map(|item| match item.validate() {
+            Ok(_) => Ok(item.to_model(customer_id as i64, time_now)),
+            Err(_) => Err(Error::Invalid),
+        })
+        .collect();
+
+    if mode == BatchMode::Atomic {
+        if let Some(index) = items.iter().position(|item| item.is_err()) {
+            return HttpResponse::UnprocessableEntity().json(BatchFailureResponse{
+                index,
+                error: Error::Invalid.code().to_string(),
+            })
+        }
+    }
+
+    let results = db.create_transactions_batch(
+        customer_id as i64,
+        items,
+        mode == BatchMode::Atomic,
+    ).await;
+
+    let results = match results {
+        Ok(results) => results,
+        Err(Error::NotFound) => return HttpResponse::NotFound().into(),
+        Err(_) => return HttpResponse::InternalServerError().into(),
+    };
+
+    if mode == BatchMode::Atomic {
+        if let Some(Err(Error::Default)) = results.last() {
+            return HttpResponse::InternalServerError().into()
+        }
+
+        if let Some(Err(err)) = results.last() {
+            return HttpResponse::UnprocessableEntity().json(BatchFailureResponse{
+                index: results.len() - 1,
+                error: err.code().to_string(),
+            })
+        }
+    }
+
+    let mut response = HttpResponse::Ok();
+    append_lsn(&mut response, &db).await;
+    response.json(BatchResponse::from_results(mode, &results))
+}
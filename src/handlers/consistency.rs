@@ -0,0 +1,20 @@
+use actix_web::{HttpRequest, HttpResponseBuilder};
+
+use crate::db::Database;
+
+// Writes answer with the primary's LSN in this header when replicas are
+// configured; reads that send it back are only served by a replica that has
+// replayed that far.
+pub const LSN_HEADER: &str = "x-lsn";
+
+pub fn read_after(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(LSN_HEADER)
+        .and_then(|value| value.to_str().ok())
+}
+
+pub async fn append_lsn(response: &mut HttpResponseBuilder, db: &Database) {
+    if let Some(lsn) = db.current_lsn().await {
+        response.insert_header((LSN_HEADER, lsn));
+    }
+}
@@ -0,0 +1,16 @@
+use actix_web::{get, HttpResponse};
+use utoipa::OpenApi;
+
+use crate::openapi::{ApiDoc, VIEWER};
+
+#[get("/openapi.json")]
+pub async fn get_openapi() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+#[get("/docs")]
+pub async fn get_docs() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(VIEWER)
+}
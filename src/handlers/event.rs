@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::{get, HttpRequest, HttpResponse, web::Bytes, web::Path};
+use actix_web::web::Data;
+use futures_util::stream;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::db::{Database, Listener, RESUME_PAGE};
+use crate::models::{CustomerURL, TransactionNotification};
+use crate::responses::TransactionEventResponse;
+
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+struct EventStream {
+    db: Data<Database>,
+    customer_id: i64,
+    last_seq: i64,
+    backlog: VecDeque<TransactionNotification>,
+    // the last backlog page was full, there may be more to read
+    paging: bool,
+    receiver: Receiver<Arc<TransactionNotification>>,
+}
+
+#[utoipa::path(
+    tag = "eventos",
+    params(
+        ("customer_id" = i32, Path, description = "customer id"),
+        ("Last-Event-ID" = Option<i64>, Header, description = "resume after this event id"),
+    ),
+    responses(
+        (status = 200, description = "server-sent `transacao` events, each `data` line one TransactionEventResponse", content(
+            ("text/event-stream" = TransactionEventResponse),
+        )),
+        (status = 404, description = "unknown customer"),
+        (status = 500, description = "the missed events could not be read"),
+    ),
+)]
+#[get("/clientes/{customer_id}/eventos")]
+pub async fn stream_events(
+    req: HttpRequest,
+    customer_url: Path<CustomerURL>,
+    db: Data<Database>,
+    listener: Data<Listener>,
+) -> HttpResponse {
+    let customer_id = customer_url.customer_id;
+
+    if customer_id < 0 {
+        return HttpResponse::NotFound().into()
+    }
+
+    if db.get_customer_by_id(customer_id, None).await.is_err() {
+        return HttpResponse::NotFound().into()
+    }
+
+    let last_event_id = req.headers()
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok());
+
+    // subscribe before reading the backlog so nothing falls in between,
+    // duplicates are skipped by comparing sequence numbers
+    let receiver = listener.subscribe();
+
+    let backlog = match last_event_id {
+        Some(seq) => match db.get_notifications_since(customer_id, seq).await {
+            Ok(backlog) => backlog,
+            Err(_) => return HttpResponse::InternalServerError().into(),
+        },
+        None => vec![],
+    };
+
+    let state = EventStream{
+        db,
+        customer_id: customer_id as i64,
+        last_seq: last_event_id.unwrap_or(0),
+        paging: backlog.len() as i64 == RESUME_PAGE,
+        backlog: backlog.into(),
+        receiver,
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("cache-control", "no-cache"))
+        .insert_header(("x-accel-buffering", "no"))
+        .streaming(stream::unfold(state, next_event))
+}
+
+// Reads the missed events page by page until caught up, then follows the live
+// ones; live events received meanwhile wait in the receiver and are skipped
+// by sequence number once sent from the backlog.
+async fn next_event(mut state: EventStream) -> Option<(Result<Bytes, actix_web::Error>, EventStream)> {
+    if state.backlog.is_empty() && state.paging {
+        let customer_id = state.customer_id as i32;
+
+        match state.db.get_notifications_since(customer_id, state.last_seq).await {
+            Ok(page) => {
+                state.paging = page.len() as i64 == RESUME_PAGE;
+                state.backlog = page.into();
+            }
+            // end the stream so the client resumes with Last-Event-ID
+            Err(_) => return None,
+        }
+    }
+
+    if let Some(notification) = state.backlog.pop_front() {
+        state.last_seq = notification.seq;
+        return Some((Ok(format_event(&notification)), state))
+    }
+
+    loop {
+        let received = tokio::time::timeout(KEEP_ALIVE, state.receiver.recv()).await;
+
+        match received {
+            Err(_) => return Some((Ok(Bytes::from_static(b": keep-alive\n\n")), state)),
+            Ok(Ok(notification)) => {
+                if notification.event.customer_id != state.customer_id || notification.seq <= state.last_seq {
+                    continue
+                }
+                state.last_seq = notification.seq;
+                return Some((Ok(format_event(&notification)), state))
+            }
+            Ok(Err(RecvError::Lagged(_))) => {
+                // events were dropped, end the stream so the client resumes with Last-Event-ID
+                return None
+            }
+            Ok(Err(RecvError::Closed)) => return None,
+        }
+    }
+}
+
+fn format_event(notification: &TransactionNotification) -> Bytes {
+    let data = serde_json::to_string(
+        &TransactionEventResponse::from_model(notification.seq, &notification.event),
+    ).unwrap();
+
+    Bytes::from(format!("id: {}\nevent: transacao\ndata: {}\n\n", notification.seq, data))
+}
@@ -0,0 +1,46 @@
+use actix_web::{get, HttpResponse, web::Path, web::Query};
+use actix_web::web::Data;
+use validator::{Validate};
+
+use crate::db::Database;
+use crate::models::CustomerURL;
+use crate::requests::FeeSimulationQuery;
+use crate::responses::FeeSimulationResponse;
+
+#[utoipa::path(
+    tag = "encargos",
+    params(("customer_id" = i32, Path, description = "customer id"), FeeSimulationQuery),
+    responses(
+        (status = 200, body = FeeSimulationResponse),
+        (status = 404, description = "unknown customer"),
+        (status = 422, description = "invalid simulation"),
+    ),
+)]
+#[get("/clientes/{customer_id}/encargos/simulacao")]
+pub async fn simulate_fees(
+    customer_url: Path<CustomerURL>,
+    query: Query<FeeSimulationQuery>,
+    db: Data<Database>,
+) -> HttpResponse {
+    let is_valid = query.validate();
+    if is_valid.is_err() {
+        return HttpResponse::UnprocessableEntity().into()
+    }
+
+    let customer_id = customer_url.customer_id;
+
+    if customer_id < 0 {
+        return HttpResponse::NotFound().into()
+    }
+
+    let customer_opt = db.get_customer_by_id(customer_id, None).await;
+    if customer_opt.is_err() {
+        return HttpResponse::NotFound().into()
+    }
+
+    let customer = customer_opt.unwrap();
+
+    let simulation = db.fees.simulate(customer.balance, customer.limit, query.days, query.debits);
+
+    HttpResponse::Ok().json(FeeSimulationResponse::from_model(&db.fees, &simulation))
+}
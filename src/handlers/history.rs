@@ -0,0 +1,48 @@
+use actix_web::{get, HttpRequest, HttpResponse, web::Path, web::Query};
+use actix_web::web::Data;
+use validator::{Validate};
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::models::CustomerURL;
+use crate::requests::HistoryQuery;
+use crate::responses::GetHistoryResponse;
+use super::read_after;
+
+#[utoipa::path(
+    tag = "transacoes",
+    params(("customer_id" = i32, Path, description = "customer id"), HistoryQuery),
+    responses(
+        (status = 200, body = GetHistoryResponse),
+        (status = 404, description = "unknown customer"),
+        (status = 422, description = "invalid filter"),
+    ),
+)]
+#[get("/clientes/{customer_id}/transacoes")]
+pub async fn get_history(
+    req: HttpRequest,
+    customer_url: Path<CustomerURL>,
+    query: Query<HistoryQuery>,
+    db: Data<Database>,
+) -> HttpResponse {
+    let is_valid = query.validate();
+    if is_valid.is_err() {
+        return HttpResponse::UnprocessableEntity().into()
+    }
+
+    let customer_id = customer_url.customer_id;
+
+    if customer_id < 0 {
+        return HttpResponse::NotFound().into()
+    }
+
+    let transactions = db.get_transactions(customer_id, &query.to_filter(), read_after(&req)).await;
+
+    match transactions {
+        Ok(transactions) => {
+            HttpResponse::Ok().json(GetHistoryResponse::from_models(&transactions))
+        }
+        Err(Error::NotFound) => HttpResponse::NotFound().into(),
+        Err(_) => HttpResponse::InternalServerError().into(),
+    }
+}
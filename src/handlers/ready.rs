@@ -0,0 +1,21 @@
+use actix_web::{get, HttpResponse};
+use actix_web::web::Data;
+
+use crate::db::Database;
+
+// 200 once the storage answers, what `nilapi proxy` health checks poll
+#[utoipa::path(
+    tag = "operacao",
+    responses(
+        (status = 200, description = "storage is reachable"),
+        (status = 503, description = "storage is not reachable"),
+    ),
+)]
+#[get("/ready")]
+pub async fn get_ready(db: Data<Database>) -> HttpResponse {
+    if db.is_ready().await {
+        return HttpResponse::Ok().finish()
+    }
+
+    HttpResponse::ServiceUnavailable().finish()
+}
@@ -0,0 +1,47 @@
+use actix_web::{post, HttpResponse, web::Json, web::Path};
+use actix_web::web::Data;
+use validator::{Validate};
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::models::CustomerURL;
+use crate::requests::SchedulePayload;
+use crate::responses::CreateScheduleResponse;
+
+#[utoipa::path(
+    tag = "agendamentos",
+    params(("customer_id" = i32, Path, description = "customer id")),
+    request_body = SchedulePayload,
+    responses(
+        (status = 201, body = CreateScheduleResponse),
+        (status = 404, description = "unknown customer"),
+        (status = 422, description = "invalid payload or a date in the past"),
+    ),
+)]
+#[post("/clientes/{customer_id}/agendamentos")]
+pub async fn create_schedule(
+    customer_url: Path<CustomerURL>,
+    payload: Json<SchedulePayload>,
+    db: Data<Database>,
+) -> HttpResponse {
+    let is_valid = payload.validate();
+    if is_valid.is_err() {
+        return HttpResponse::UnprocessableEntity().into()
+    }
+
+    let customer_id = customer_url.customer_id;
+
+    if customer_id < 0 {
+        return HttpResponse::NotFound().into()
+    }
+
+    let schedule = db.create_schedule(payload.to_model(customer_id)).await;
+
+    match schedule {
+        Ok(schedule) => {
+            HttpResponse::Created().json(CreateScheduleResponse::from_model(&schedule))
+        }
+        Err(Error::NotFound) => HttpResponse::NotFound().into(),
+        Err(_) => HttpResponse::InternalServerError().into(),
+    }
+}
@@ -0,0 +1,117 @@
+use actix_web::{get, HttpRequest, HttpResponse, web::Payload};
+use actix_web::web::Data;
+use actix_ws::{Message, MessageStream, Session};
+use futures_util::StreamExt;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::db::{Database, Listener};
+use crate::requests::TransactionCommand;
+use crate::responses::{BalanceUpdateResponse, CommandResultResponse};
+use super::admin::bearer_token;
+use super::transaction::submit_transaction;
+
+const INVALID_COMMAND: &str = "comando_invalido";
+
+// The API key is only read from the Authorization header: a query string
+// ends up in access and proxy logs.
+#[get("/ws/transacoes")]
+pub async fn transactions_socket(
+    req: HttpRequest,
+    body: Payload,
+    db: Data<Database>,
+    listener: Data<Listener>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let customer_id = match bearer_token(&req) {
+        Some(token) => db.get_customer_by_api_key(token).await,
+        None => None,
+    };
+
+    let Some(customer_id) = customer_id else {
+        return Ok(HttpResponse::Unauthorized().into())
+    };
+
+    let (response, session, messages) = actix_ws::handle(&req, body)?;
+
+    actix_web::rt::spawn(run_session(
+        db.get_ref().clone(),
+        listener.get_ref().clone(),
+        customer_id,
+        session,
+        messages,
+    ));
+
+    Ok(response)
+}
+
+async fn run_session(
+    db: Database,
+    listener: Listener,
+    customer_id: i32,
+    mut session: Session,
+    mut messages: MessageStream,
+) {
+    let mut notifications = listener.subscribe();
+
+    loop {
+        tokio::select! {
+            message = messages.next() => {
+                let reply = match message {
+                    Some(Ok(Message::Text(text))) => handle_command(&db, customer_id, &text).await,
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return
+                        }
+                        continue
+                    }
+                    Some(Ok(Message::Close(reason))) => {
+                        let _ = session.close(reason).await;
+                        return
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(_)) | None => return,
+                };
+
+                if session.text(reply).await.is_err() {
+                    return
+                }
+            }
+            notification = notifications.recv() => {
+                let notification = match notification {
+                    Ok(notification) => notification,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+
+                if notification.event.customer_id != customer_id as i64 {
+                    continue
+                }
+
+                let update = serde_json::to_string(
+                    &BalanceUpdateResponse::from_notification(&notification),
+                ).unwrap();
+
+                if session.text(update).await.is_err() {
+                    return
+                }
+            }
+        }
+    }
+}
+
+async fn handle_command(db: &Database, customer_id: i32, text: &str) -> String {
+    let response = match serde_json::from_str::<TransactionCommand>(text) {
+        Ok(command) => {
+            let result = submit_transaction(db, customer_id, &command.payload).await;
+            CommandResultResponse::from_result(Some(command.id), &result)
+        }
+        Err(_) => {
+            // still echo the id when the rest of the command is malformed
+            let id = serde_json::from_str::<serde_json::Value>(text)
+                .ok()
+                .and_then(|value| value.get("id")?.as_str().map(String::from));
+            CommandResultResponse::from_error(id, INVALID_COMMAND)
+        }
+    };
+
+    serde_json::to_string(&response).unwrap()
+}
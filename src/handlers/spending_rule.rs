@@ -0,0 +1,80 @@
+use actix_web::{get, put, HttpRequest, HttpResponse, web::Json, web::Path};
+use actix_web::web::Data;
+use validator::{Validate};
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::models::CustomerURL;
+use crate::requests::SpendingRulesPayload;
+use crate::responses::SpendingRulesResponse;
+use super::admin::{AdminToken, authorize_customer};
+
+#[utoipa::path(
+    tag = "regras",
+    params(("customer_id" = i32, Path, description = "customer id")),
+    responses(
+        (status = 200, body = SpendingRulesResponse),
+        (status = 404, description = "unknown customer"),
+    ),
+)]
+#[get("/clientes/{customer_id}/regras")]
+pub async fn get_spending_rules(
+    customer_url: Path<CustomerURL>,
+    db: Data<Database>,
+) -> HttpResponse {
+    let customer_id = customer_url.customer_id;
+
+    if customer_id < 0 {
+        return HttpResponse::NotFound().into()
+    }
+
+    match db.get_spending_rules(customer_id).await {
+        Ok(rules) => HttpResponse::Ok().json(SpendingRulesResponse::from_model(&rules)),
+        Err(Error::NotFound) => HttpResponse::NotFound().into(),
+        Err(_) => HttpResponse::InternalServerError().into(),
+    }
+}
+
+#[utoipa::path(
+    tag = "regras",
+    params(("customer_id" = i32, Path, description = "customer id")),
+    request_body = SpendingRulesPayload,
+    responses(
+        (status = 200, body = SpendingRulesResponse),
+        (status = 401, description = "missing the customer's API key or the admin token"),
+        (status = 404, description = "unknown customer"),
+        (status = 422, description = "invalid rules"),
+    ),
+)]
+#[put("/clientes/{customer_id}/regras")]
+pub async fn save_spending_rules(
+    req: HttpRequest,
+    customer_url: Path<CustomerURL>,
+    payload: Json<SpendingRulesPayload>,
+    admin_token: Data<AdminToken>,
+    db: Data<Database>,
+) -> HttpResponse {
+    if !authorize_customer(&req, &admin_token, &db, customer_url.customer_id).await {
+        return HttpResponse::Unauthorized().into()
+    }
+
+    let is_valid = payload.validate();
+    if is_valid.is_err() {
+        return HttpResponse::UnprocessableEntity().into()
+    }
+
+    let customer_id = customer_url.customer_id;
+
+    if customer_id < 0 {
+        return HttpResponse::NotFound().into()
+    }
+
+    let rules = payload.to_model();
+
+    match db.save_spending_rules(customer_id, &rules).await {
+        Ok(_) => HttpResponse::Ok().json(SpendingRulesResponse::from_model(&rules)),
+        Err(Error::NotFound) => HttpResponse::NotFound().into(),
+        Err(Error::Invalid) => HttpResponse::UnprocessableEntity().into(),
+        Err(_) => HttpResponse::InternalServerError().into(),
+    }
+}
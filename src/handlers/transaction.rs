@@ -0,0 +1,30 @@
+use chrono::Utc;
+use validator::{Validate};
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::models::transaction::CustomerLean;
+use crate::requests::TransactionPayload;
+
+// Shared by every transport that accepts transaction commands, so HTTP,
+// WebSocket and gRPC clients get the same validation and the same ledger path.
+pub async fn submit_transaction(
+    db: &Database,
+    customer_id: i32,
+    payload: &TransactionPayload,
+) -> Result<CustomerLean, Error> {
+    let is_valid = payload.validate();
+    if is_valid.is_err() {
+        return Err(Error::Invalid)
+    }
+
+    if customer_id < 0 {
+        return Err(Error::NotFound)
+    }
+
+    let time_now = Utc::now();
+
+    db.create_transaction(
+        payload.to_model(customer_id as i64, time_now),
+    ).await
+}
@@ -0,0 +1,157 @@
+use std::fmt::{Debug, Display};
+
+use actix_web::{get, post, HttpRequest, HttpResponse, web::Json, web::Path, web::Query};
+use actix_web::error::InternalError;
+use actix_web::web::Data;
+use validator::{Validate};
+
+use crate::db::Database;
+use crate::errors::Error;
+use crate::models::CustomerURL;
+use crate::requests::{HistoryQueryV2, TransactionPayloadV2};
+use crate::responses::{
+    CreateTransactionResponseV2, ErrorResponseV2, GetHistoryResponseV2, GetStatementResponseV2,
+};
+use super::{append_lsn, read_after, submit_transaction};
+
+fn error_response(err: Error) -> HttpResponse {
+    let body = ErrorResponseV2::from_error(&err);
+
+    match err {
+        Error::NotFound => HttpResponse::NotFound().json(body),
+        Error::Default => HttpResponse::InternalServerError().json(body),
+        _ => HttpResponse::UnprocessableEntity().json(body),
+    }
+}
+
+// error handler for the v2 scope's Json, Path and Query extractors, so a
+// malformed request gets an ErrorResponseV2 instead of the v1 debug dump
+pub fn reject_request<E: Debug + Display + 'static>(err: E, _: &HttpRequest) -> actix_web::Error {
+    InternalError::from_response(err, error_response(Error::Invalid)).into()
+}
+
+#[utoipa::path(
+    tag = "v2",
+    context_path = "/v2",
+    params(("customer_id" = i32, Path, description = "customer id")),
+    request_body = TransactionPayloadV2,
+    responses(
+        (status = 200, body = CreateTransactionResponseV2),
+        (status = 404, description = "unknown customer", body = ErrorResponseV2),
+        (status = 422, description = "invalid payload, insufficient limit or a spending rule violation", body = ErrorResponseV2),
+        (status = 500, description = "database failure", body = ErrorResponseV2),
+    ),
+)]
+#[post("/customers/{customer_id}/transactions")]
+pub async fn create_transaction_v2(
+    customer_url: Path<CustomerURL>,
+    payload: Json<TransactionPayloadV2>,
+    db: Data<Database>,
+) -> HttpResponse {
+    let result = submit_transaction(
+        &db,
+        customer_url.customer_id,
+        &payload.to_payload(),
+    ).await;
+
+    match result {
+        Ok(customer) => {
+            let mut response = HttpResponse::Ok();
+            append_lsn(&mut response, &db).await;
+            response.json(CreateTransactionResponseV2::from_model(&customer))
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+#[utoipa::path(
+    tag = "v2",
+    context_path = "/v2",
+    params(("customer_id" = i32, Path, description = "customer id")),
+    responses(
+        (status = 200, body = GetStatementResponseV2),
+        (status = 404, description = "unknown customer", body = ErrorResponseV2),
+        (status = 500, description = "database failure", body = ErrorResponseV2),
+    ),
+)]
+#[get("/customers/{customer_id}/statement")]
+pub async fn get_statement_v2(
+    req: HttpRequest,
+    customer_url: Path<CustomerURL>,
+    db: Data<Database>,
+) -> HttpResponse {
+    let customer_id = customer_url.customer_id;
+
+    if customer_id < 0 {
+        return error_response(Error::NotFound)
+    }
+
+    match db.get_customer_by_id(customer_id, read_after(&req)).await {
+        Ok(customer) => HttpResponse::Ok().json(GetStatementResponseV2::from_customer(&customer)),
+        Err(err) => error_response(err),
+    }
+}
+
+#[utoipa::path(
+    tag = "v2",
+    context_path = "/v2",
+    params(("customer_id" = i32, Path, description = "customer id"), HistoryQueryV2),
+    responses(
+        (status = 200, body = GetHistoryResponseV2),
+        (status = 404, description = "unknown customer", body = ErrorResponseV2),
+        (status = 422, description = "invalid filter", body = ErrorResponseV2),
+        (status = 500, description = "database failure", body = ErrorResponseV2),
+    ),
+)]
+#[get("/customers/{customer_id}/transactions")]
+pub async fn get_history_v2(
+    req: HttpRequest,
+    customer_url: Path<CustomerURL>,
+    query: Query<HistoryQueryV2>,
+    db: Data<Database>,
+) -> HttpResponse {
+    let query = query.to_query();
+
+    let is_valid = query.validate();
+    if is_valid.is_err() {
+        return error_response(Error::Invalid)
+    }
+
+    let customer_id = customer_url.customer_id;
+
+    if customer_id < 0 {
+        return error_response(Error::NotFound)
+    }
+
+    match db.get_transactions(customer_id, &query.to_filter(), read_after(&req)).await {
+        Ok(transactions) => HttpResponse::Ok().json(GetHistoryResponseV2::from_models(&transactions)),
+        Err(err) => error_response(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::to_bytes;
+    use actix_web::error::JsonPayloadError;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn malformed_requests_get_a_v2_error() {
+        let req = TestRequest::default().to_http_request();
+        let response = reject_request(JsonPayloadError::ContentType, &req).error_response();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, r#"{"error":"invalid_request"}"#);
+    }
+
+    #[test]
+    fn codes_are_english() {
+        assert_eq!(ErrorResponseV2::from_error(&Error::NotFound).error, "customer_not_found");
+        assert_eq!(ErrorResponseV2::from_error(&Error::InsufficientLimit).error, "insufficient_limit");
+        assert_eq!(ErrorResponseV2::from_error(&Error::Default).error, "internal_error");
+    }
+}
@@ -0,0 +1,82 @@
+use actix_web::{delete, put, HttpRequest, HttpResponse, web::Json, web::Path};
+use actix_web::web::Data;
+use validator::{Validate};
+
+use crate::db::Database;
+use crate::jobs::webhook::WebhookTargets;
+use crate::models::CustomerURL;
+use crate::requests::WebhookPayload;
+use super::admin::{AdminToken, authorize_customer};
+
+#[utoipa::path(
+    tag = "webhook",
+    params(("customer_id" = i32, Path, description = "customer id")),
+    request_body = WebhookPayload,
+    responses(
+        (status = 204, description = "webhook saved"),
+        (status = 401, description = "missing the customer's API key or the admin token"),
+        (status = 404, description = "unknown customer"),
+        (status = 422, description = "invalid url or secret, or a private address"),
+    ),
+)]
+#[put("/clientes/{customer_id}/webhook")]
+pub async fn save_webhook(
+    req: HttpRequest,
+    customer_url: Path<CustomerURL>,
+    payload: Json<WebhookPayload>,
+    admin_token: Data<AdminToken>,
+    targets: Data<WebhookTargets>,
+    db: Data<Database>,
+) -> HttpResponse {
+    let customer_id = customer_url.customer_id;
+
+    if !authorize_customer(&req, &admin_token, &db, customer_id).await {
+        return HttpResponse::Unauthorized().into()
+    }
+
+    let is_valid = payload.validate();
+    if is_valid.is_err() || targets.check(&payload.url).await.is_err() {
+        return HttpResponse::UnprocessableEntity().into()
+    }
+
+    if customer_id < 0 {
+        return HttpResponse::NotFound().into()
+    }
+
+    match db.save_webhook(customer_id, &payload.to_model()).await {
+        Ok(_) => HttpResponse::NoContent().into(),
+        Err(_) => HttpResponse::NotFound().into()
+    }
+}
+
+#[utoipa::path(
+    tag = "webhook",
+    params(("customer_id" = i32, Path, description = "customer id")),
+    responses(
+        (status = 204, description = "webhook removed"),
+        (status = 401, description = "missing the customer's API key or the admin token"),
+        (status = 404, description = "unknown customer or no webhook"),
+    ),
+)]
+#[delete("/clientes/{customer_id}/webhook")]
+pub async fn delete_webhook(
+    req: HttpRequest,
+    customer_url: Path<CustomerURL>,
+    admin_token: Data<AdminToken>,
+    db: Data<Database>,
+) -> HttpResponse {
+    let customer_id = customer_url.customer_id;
+
+    if !authorize_customer(&req, &admin_token, &db, customer_id).await {
+        return HttpResponse::Unauthorized().into()
+    }
+
+    if customer_id < 0 {
+        return HttpResponse::NotFound().into()
+    }
+
+    match db.delete_webhook(customer_id).await {
+        Ok(_) => HttpResponse::NoContent().into(),
+        Err(_) => HttpResponse::NotFound().into()
+    }
+}
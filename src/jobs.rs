@@ -0,0 +1,3 @@
+pub mod scheduler;
+pub mod interest;
+pub mod webhook;
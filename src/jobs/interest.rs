@@ -0,0 +1,80 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::errors::Error;
+
+const INTEREST_LOCK_KEY: i64 = 0x6e69_6c02;
+
+pub fn spawn(db: Database, config: &Config) {
+    if db.fees.daily_interest_bps <= 0 {
+        return
+    }
+
+    let interval = StdDuration::from_secs(config.fees_interval_secs);
+
+    actix_web::rt::spawn(async move {
+        let mut leader = None;
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            if !db.hold_leadership(&mut leader, INTEREST_LOCK_KEY).await {
+                continue
+            }
+
+            accrue(&db).await;
+        }
+    });
+}
+
+// Claims every closed day not claimed yet, including those the job was down
+// for, charging each on the balance at the end of that day. A day is only
+// finished once all of its customers are claimed, so a failed run repeats it.
+// Then posts every claimed day not posted yet.
+async fn accrue(db: &Database) {
+    let now = Utc::now();
+    let until = (now - Duration::days(1)).date_naive();
+
+    let dates = match db.get_interest_dates(until).await {
+        Ok(dates) => dates,
+        Err(err) => {
+            log::warn!("fail to load interest dates: {:?}", err);
+            vec![]
+        }
+    };
+
+    for accrual_date in dates {
+        if let Err(err) = claim_day(db, accrual_date, now).await {
+            log::warn!("interest of {} not claimed: {:?}", accrual_date, err);
+            break
+        }
+    }
+
+    let accruals = match db.get_unposted_interest_accruals().await {
+        Ok(accruals) => accruals,
+        Err(err) => {
+            log::warn!("fail to load unposted interest: {:?}", err);
+            return
+        }
+    };
+
+    for (customer_id, accrual_date) in accruals {
+        if let Err(err) = db.post_interest_accrual(customer_id, accrual_date).await {
+            log::warn!("interest of {} for customer {} not posted: {:?}", accrual_date, customer_id, err);
+        }
+    }
+}
+
+async fn claim_day(db: &Database, accrual_date: NaiveDate, now: DateTime<Utc>) -> Result<(), Error> {
+    for (customer_id, balance) in db.get_overdrawn_customers(accrual_date).await? {
+        if let Some(interest) = db.fees.interest_for(customer_id as i64, balance, now) {
+            db.claim_interest_accrual(customer_id, accrual_date, interest.amount).await?;
+        }
+    }
+
+    db.finish_interest_run(accrual_date).await
+}
@@ -0,0 +1,86 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{Duration, Utc};
+
+use crate::config::Config;
+use crate::db::{Database, ScheduledRun};
+use crate::errors::Error;
+use crate::models::Schedule;
+
+const SCHEDULER_LOCK_KEY: i64 = 0x6e69_6c01;
+const BATCH_SIZE: i64 = 100;
+
+pub fn spawn(db: Database, config: &Config) {
+    let interval = StdDuration::from_millis(config.scheduler_interval_ms);
+    let max_retries = config.scheduler_max_retries;
+    let retry_delay = Duration::seconds(config.scheduler_retry_delay_secs);
+
+    actix_web::rt::spawn(async move {
+        let mut leader = None;
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            if !db.hold_leadership(&mut leader, SCHEDULER_LOCK_KEY).await {
+                continue
+            }
+
+            run_due(&db, max_retries, retry_delay).await;
+        }
+    });
+}
+
+async fn run_due(db: &Database, max_retries: i32, retry_delay: Duration) {
+    let now = Utc::now();
+
+    let schedules = match db.get_due_schedules(now, BATCH_SIZE).await {
+        Ok(schedules) => schedules,
+        Err(err) => {
+            log::warn!("fail to load due schedules: {:?}", err);
+            return
+        }
+    };
+
+    for schedule in schedules {
+        let result = match db.post_scheduled(&schedule.to_transaction(Utc::now())).await {
+            Ok(ScheduledRun::Posted | ScheduledRun::AlreadyPosted) => db.advance_schedule(&schedule, schedule.next_run()).await,
+            Ok(ScheduledRun::InsufficientLimit) => {
+                handle_failure(db, &schedule, "insufficient_limit", max_retries, retry_delay).await
+            }
+            Err(err) => {
+                let reason = match err {
+                    Error::NotFound => "customer_not_found",
+                    Error::Default => "error",
+                    Error::InsufficientLimit => "insufficient_limit",
+                    Error::Invalid => "invalid",
+                    Error::RuleViolation(rule) => rule.code(),
+                };
+
+                handle_failure(db, &schedule, reason, max_retries, retry_delay).await
+            }
+        };
+
+        // the schedule is still due and gets picked up again on the next tick
+        if let Err(err) = result {
+            log::warn!("fail to update schedule {}: {:?}", schedule.id, err);
+        }
+    }
+}
+
+async fn handle_failure(
+    db: &Database,
+    schedule: &Schedule,
+    reason: &str,
+    max_retries: i32,
+    retry_delay: Duration,
+) -> Result<(), Error> {
+    db.log_schedule_failure(schedule, reason).await?;
+
+    if schedule.attempts + 1 < max_retries {
+        return db.retry_schedule(schedule, Utc::now() + retry_delay).await
+    }
+
+    log::warn!("schedule {} gave up after {} attempts: {}", schedule.id, max_retries, reason);
+    db.advance_schedule(schedule, schedule.next_run()).await
+}
@@ -0,0 +1,503 @@
+use std::error::Error as StdError;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration as StdDuration;
+
+use actix_tls::connect::{Connector as TcpConnector, Resolve, Resolver};
+use awc::{Client, Connector};
+use chrono::{Duration, Utc};
+use futures_util::future::LocalBoxFuture;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use url::{Host, Url};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::errors::Error;
+use crate::models::OutboxEvent;
+use crate::responses::TransactionEventResponse;
+
+const WEBHOOK_LOCK_KEY: i64 = 0x6e69_6c03;
+const BATCH_SIZE: i64 = 100;
+const MAX_BACKOFF_MS: i64 = 60 * 60 * 1000;
+
+#[derive(Clone, Copy)]
+struct Delivery {
+    max_attempts: i32,
+    backoff_ms: i64,
+    timeout: StdDuration,
+    targets: WebhookTargets,
+}
+
+// Deliveries leave from inside our network, so by default a webhook may only
+// point at public addresses. WEBHOOK_ALLOW_PRIVATE lifts that for receivers
+// on the same private network.
+#[derive(Clone, Copy)]
+pub struct WebhookTargets {
+    allow_private: bool,
+}
+
+impl WebhookTargets {
+    pub fn from_config(config: &Config) -> WebhookTargets {
+        WebhookTargets{ allow_private: config.webhook_allow_private }
+    }
+
+    // Redirects are not followed, a receiver could send the delivery on to a
+    // private address. Names are resolved by PublicResolver, so the connection
+    // goes to the addresses that passed the check.
+    fn client(&self, timeout: StdDuration) -> Client {
+        let builder = Client::builder().timeout(timeout).disable_redirects();
+
+        if self.allow_private {
+            return builder.finish()
+        }
+
+        let connector = Connector::new()
+            .connector(TcpConnector::new(Resolver::custom(PublicResolver)).service());
+
+        builder.connector(connector).finish()
+    }
+
+    // checked when the webhook is saved and again before every delivery;
+    // names are checked once more by the resolver the delivery connects with
+    pub async fn check(&self, url: &str) -> Result<(), String> {
+        let url = Url::parse(url).map_err(|err| err.to_string())?;
+
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(format!("scheme {} not allowed", url.scheme()))
+        }
+
+        if self.allow_private {
+            return Ok(())
+        }
+
+        let port = url.port_or_known_default().unwrap_or(80);
+        let addresses = match url.host() {
+            Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+            Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+            Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port)).await
+                .map_err(|err| err.to_string())?
+                .map(|address| address.ip())
+                .collect(),
+            None => return Err(String::from("missing host")),
+        };
+
+        match addresses.into_iter().find(|ip| !is_public(*ip)) {
+            Some(ip) => Err(format!("address {} not allowed", ip)),
+            None => Ok(()),
+        }
+    }
+}
+
+// Looks a webhook host up once and fails when any of its addresses is not
+// public, instead of leaving a second lookup to the client that a short TTL
+// could point elsewhere.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn lookup<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> LocalBoxFuture<'a, Result<Vec<SocketAddr>, Box<dyn StdError>>> {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await?.collect();
+
+            match addresses.iter().find(|address| !is_public(address.ip())) {
+                Some(address) => Err(format!("address {} not allowed", address.ip()).into()),
+                None => Ok(addresses),
+            }
+        })
+    }
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            let this_network = a == 0;
+            let shared = a == 100 && (64..128).contains(&b);
+            let benchmarking = a == 198 && (18..20).contains(&b);
+            // 240.0.0.0/4 is reserved and includes the broadcast address
+            let reserved = a >= 240;
+
+            !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_multicast()
+                || ip.is_documentation() || this_network || shared || benchmarking || reserved)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let [first, second, ..] = ip.segments();
+                let unique_local = first & 0xfe00 == 0xfc00;
+                let link_local = first & 0xffc0 == 0xfe80;
+                let documentation = first == 0x2001 && second == 0x0db8;
+
+                !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast()
+                    || unique_local || link_local || documentation)
+            }
+        },
+    }
+}
+
+enum Step {
+    Complete,
+    Retry{ backoff_ms: i64, error: String },
+    DeadLetter(String),
+}
+
+pub fn spawn(db: Database, config: &Config) {
+    let interval = StdDuration::from_millis(config.webhook_interval_ms);
+    let delivery = Delivery{
+        max_attempts: config.webhook_max_attempts,
+        backoff_ms: config.webhook_backoff_ms,
+        timeout: StdDuration::from_millis(config.webhook_timeout_ms),
+        targets: WebhookTargets::from_config(config),
+    };
+
+    actix_web::rt::spawn(async move {
+        let client = delivery.targets.client(delivery.timeout);
+        let mut leader = None;
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            if !db.hold_leadership(&mut leader, WEBHOOK_LOCK_KEY).await {
+                continue
+            }
+
+            let events = match db.get_pending_events(BATCH_SIZE).await {
+                Ok(events) => events,
+                Err(err) => {
+                    log::warn!("fail to load pending webhook events: {:?}", err);
+                    continue
+                }
+            };
+
+            for event in events {
+                let id = event.id;
+
+                // the event stays in the outbox and is retried on a later tick
+                if let Err(err) = dispatch(&db, &client, delivery, event).await {
+                    log::warn!("fail to update webhook event {}: {:?}", id, err);
+                }
+            }
+        }
+    });
+}
+
+async fn dispatch(db: &Database, client: &Client, delivery: Delivery, event: OutboxEvent) -> Result<(), Error> {
+    let (Some(url), Some(secret)) = (&event.url, &event.secret) else {
+        return db.complete_event(&event).await
+    };
+
+    let result = deliver(client, delivery.targets, url, secret, &event).await;
+
+    match next_step(delivery, event.attempts, result) {
+        Step::Complete => db.complete_event(&event).await,
+        Step::Retry{ backoff_ms, error } => {
+            db.reschedule_event(&event, Utc::now() + Duration::milliseconds(backoff_ms), &error).await
+        }
+        Step::DeadLetter(error) => {
+            log::warn!("webhook event {} dead lettered: {}", event.id, error);
+            db.dead_letter_event(&event, &error).await
+        }
+    }
+}
+
+fn next_step(delivery: Delivery, attempts: i32, result: Result<(), String>) -> Step {
+    let Err(error) = result else {
+        return Step::Complete
+    };
+
+    if attempts + 1 >= delivery.max_attempts {
+        return Step::DeadLetter(error)
+    }
+
+    let backoff_ms = delivery.backoff_ms
+        .saturating_mul(1 << attempts.min(30))
+        .min(MAX_BACKOFF_MS);
+
+    Step::Retry{ backoff_ms, error }
+}
+
+async fn deliver(
+    client: &Client,
+    targets: WebhookTargets,
+    url: &str,
+    secret: &str,
+    event: &OutboxEvent,
+) -> Result<(), String> {
+    targets.check(url).await?;
+
+    let body = serde_json::to_string(
+        &TransactionEventResponse::from_model(event.id, &event.event),
+    ).unwrap();
+    let timestamp = Utc::now().timestamp().to_string();
+
+    let response = client.post(url)
+        .insert_header(("content-type", "application/json"))
+        .insert_header(("x-nilapi-event-id", event.id.to_string()))
+        .insert_header(("x-nilapi-timestamp", timestamp.as_str()))
+        .insert_header(("x-nilapi-signature", sign(secret, &timestamp, &body)))
+        .send_body(body)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if response.status().is_redirection() {
+        return Err(format!("status {}, redirects are not followed", response.status()))
+    }
+
+    if !response.status().is_success() {
+        return Err(format!("status {}", response.status()))
+    }
+
+    Ok(())
+}
+
+// receivers recompute HMAC-SHA256 over `{timestamp}.{body}` with the shared secret
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+    use actix_web::dev::ServerHandle;
+    use actix_web::http::StatusCode;
+
+    use crate::models::TransactionEvent;
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef";
+
+    struct Request {
+        timestamp: Option<String>,
+        signature: Option<String>,
+        body: String,
+    }
+
+    #[derive(Clone, Default)]
+    struct Received(Arc<Mutex<Vec<Request>>>);
+
+    async fn stub(status: StatusCode) -> (String, Received, ServerHandle) {
+        stub_with(status, None).await
+    }
+
+    // answers every request with `status`, and `location` when set, and keeps
+    // its timestamp, signature and body
+    async fn stub_with(status: StatusCode, location: Option<String>) -> (String, Received, ServerHandle) {
+        let received = Received::default();
+        let state = received.clone();
+
+        let server = HttpServer::new(move || {
+            let state = state.clone();
+            let location = location.clone();
+
+            App::new().default_service(web::to(move |req: HttpRequest, body: String| {
+                let state = state.clone();
+                let location = location.clone();
+                async move {
+                    let header = |name| req.headers().get(name).and_then(|value| value.to_str().ok()).map(String::from);
+                    state.0.lock().unwrap().push(Request{
+                        timestamp: header("x-nilapi-timestamp"),
+                        signature: header("x-nilapi-signature"),
+                        body,
+                    });
+                    let mut response = HttpResponse::build(status);
+                    if let Some(location) = location {
+                        response.insert_header(("location", location));
+                    }
+                    response.finish()
+                }
+            }))
+        })
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+
+        let url = format!("http://{}/hook", server.addrs()[0]);
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        (url, received, handle)
+    }
+
+    fn delivery(allow_private: bool) -> Delivery {
+        Delivery{
+            max_attempts: 3,
+            backoff_ms: 1000,
+            timeout: StdDuration::from_secs(5),
+            targets: WebhookTargets{ allow_private },
+        }
+    }
+
+    fn event(url: &str, attempts: i32) -> OutboxEvent {
+        OutboxEvent{
+            shard: 0,
+            id: 7,
+            customer_id: 1,
+            event: TransactionEvent{
+                customer_id: 1,
+                amount: 1000,
+                transaction_type: String::from("d"),
+                description: String::from("mercado"),
+                created_at: Utc::now(),
+                limit: 100000,
+                balance: -1000,
+            },
+            attempts,
+            url: Some(String::from(url)),
+            secret: Some(String::from(SECRET)),
+        }
+    }
+
+    async fn send(delivery: Delivery, event: &OutboxEvent) -> Result<(), String> {
+        let client = delivery.targets.client(delivery.timeout);
+        deliver(&client, delivery.targets, event.url.as_deref().unwrap(), SECRET, event).await
+    }
+
+    #[actix_web::test]
+    async fn delivery_is_signed_over_timestamp_and_body() {
+        let (url, received, server) = stub(StatusCode::NO_CONTENT).await;
+
+        let result = send(delivery(true), &event(&url, 0)).await;
+        server.stop(false).await;
+
+        assert_eq!(result, Ok(()));
+        let received = received.0.lock().unwrap();
+        assert_eq!(received.len(), 1);
+
+        let request = &received[0];
+        let timestamp = request.timestamp.as_deref().expect("missing timestamp");
+        assert_eq!(request.signature.as_deref(), Some(sign(SECRET, timestamp, &request.body).as_str()));
+        assert_ne!(sign("another secret!!", timestamp, &request.body), sign(SECRET, timestamp, &request.body));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&request.body).unwrap()["id"], 7);
+    }
+
+    #[actix_web::test]
+    async fn failed_delivery_is_retried_with_backoff() {
+        let (url, received, server) = stub(StatusCode::INTERNAL_SERVER_ERROR).await;
+
+        let result = send(delivery(true), &event(&url, 1)).await;
+        server.stop(false).await;
+
+        assert_eq!(received.0.lock().unwrap().len(), 1);
+        match next_step(delivery(true), 1, result) {
+            Step::Retry{ backoff_ms, error } => {
+                assert_eq!(backoff_ms, 2000);
+                assert_eq!(error, "status 500 Internal Server Error");
+            }
+            _ => panic!("expected a retry"),
+        }
+    }
+
+    #[actix_web::test]
+    async fn last_failed_attempt_is_dead_lettered() {
+        let (url, _, server) = stub(StatusCode::BAD_GATEWAY).await;
+
+        let result = send(delivery(true), &event(&url, 2)).await;
+        server.stop(false).await;
+
+        match next_step(delivery(true), 2, result) {
+            Step::DeadLetter(error) => assert_eq!(error, "status 502 Bad Gateway"),
+            _ => panic!("expected a dead letter"),
+        }
+    }
+
+    #[actix_web::test]
+    async fn private_targets_are_not_delivered() {
+        let (url, received, server) = stub(StatusCode::NO_CONTENT).await;
+
+        let result = send(delivery(false), &event(&url, 0)).await;
+        server.stop(false).await;
+
+        assert!(result.is_err());
+        assert!(received.0.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn targets_must_be_public_http() {
+        let targets = WebhookTargets{ allow_private: false };
+
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://localhost/hook",
+            "http://10.1.2.3/hook",
+            "http://192.168.0.10/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "ftp://8.8.8.8/hook",
+        ] {
+            assert!(targets.check(url).await.is_err(), "{} was allowed", url);
+        }
+
+        assert_eq!(targets.check("https://8.8.8.8/hook").await, Ok(()));
+        assert_eq!(WebhookTargets{ allow_private: true }.check("http://127.0.0.1/hook").await, Ok(()));
+    }
+
+    #[actix_web::test]
+    async fn redirects_are_not_followed() {
+        let (target, received, target_server) = stub(StatusCode::NO_CONTENT).await;
+        let (url, _, server) = stub_with(StatusCode::FOUND, Some(target)).await;
+
+        let result = send(delivery(true), &event(&url, 0)).await;
+        server.stop(false).await;
+        target_server.stop(false).await;
+
+        assert_eq!(result, Err(String::from("status 302 Found, redirects are not followed")));
+        assert!(received.0.lock().unwrap().is_empty());
+        assert!(matches!(next_step(delivery(true), 0, result), Step::Retry{ .. }));
+    }
+
+    #[actix_web::test]
+    async fn client_connects_only_to_public_addresses() {
+        let (url, received, server) = stub(StatusCode::NO_CONTENT).await;
+        let port = Url::parse(&url).unwrap().port().unwrap();
+
+        // skips WebhookTargets::check, as a name rebound after it would
+        let url = format!("http://localhost:{}/hook", port);
+        let refused = WebhookTargets{ allow_private: false }.client(StdDuration::from_secs(5)).get(&url).send().await;
+        let allowed = WebhookTargets{ allow_private: true }.client(StdDuration::from_secs(5)).get(&url).send().await;
+        server.stop(false).await;
+
+        assert!(refused.is_err());
+        assert!(allowed.is_ok());
+        assert_eq!(received.0.lock().unwrap().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn resolver_refuses_names_with_a_private_address() {
+        assert!(PublicResolver.lookup("localhost", 80).await.is_err());
+        assert_eq!(
+            PublicResolver.lookup("8.8.8.8", 443).await.unwrap(),
+            vec![SocketAddr::from(([8, 8, 8, 8], 443))],
+        );
+    }
+
+    #[test]
+    fn special_purpose_addresses_are_not_public() {
+        for ip in [
+            "0.0.0.0", "0.1.2.3", "10.0.0.1", "100.64.0.1", "127.0.0.1", "169.254.169.254",
+            "172.16.0.1", "192.0.2.1", "192.168.1.1", "198.18.0.1", "198.19.255.255",
+            "198.51.100.1", "203.0.113.1", "224.0.0.1", "239.255.255.250", "240.0.0.1",
+            "255.255.255.255", "::", "::1", "fc00::1", "fe80::1", "ff02::1", "2001:db8::1",
+            "::ffff:10.0.0.1", "::ffff:224.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{} is public", ip);
+        }
+
+        for ip in ["8.8.8.8", "100.128.0.1", "198.20.0.1", "223.255.255.255", "2606:4700::1111", "::ffff:1.1.1.1"] {
+            assert!(is_public(ip.parse().unwrap()), "{} is not public", ip);
+        }
+    }
+}
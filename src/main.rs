@@ -1,7 +1,8 @@
-use actix_web::{post, get, HttpResponse, HttpServer, App, web::JsonConfig, web::Json, web::Path};
+use actix_web::{post, get, HttpRequest, HttpResponse, HttpServer, App, web::JsonConfig, web::Json, web::Path, web::Query};
 use actix_web::error::InternalError;
-use actix_web::web::{Data, PathConfig};
-use chrono::Utc;
+use actix_web::dev::Service;
+use actix_web::Scope;
+use actix_web::web::{Data, PathConfig, QueryConfig, ServiceConfig, scope};
 use validator::{Validate};
 
 mod models;
@@ -11,69 +12,232 @@ mod responses;
 mod requests;
 mod errors;
 mod serializers;
+mod handlers;
+mod jobs;
+mod exports;
+mod grpc;
+mod cache;
+mod openapi;
+mod unix_socket;
+mod proxy;
+mod tls;
+mod compression;
 
 use models::{CustomerURL};
-use requests::{TransactionPayload};
+use requests::{TransactionPayload, StatementQuery};
 use config::{Config, LOGO};
-use db::Database;
+use db::{Database, Listener};
+use exports::{StatementExport, StatementFormat};
 use crate::errors::Error;
-use crate::responses::{CreateTransactionResponse, GetStatementResponse};
+use crate::responses::{CreateTransactionResponse, GetStatementResponse, GetPeriodStatementResponse, RuleViolationResponse};
 
+#[utoipa::path(
+    tag = "transacoes",
+    params(("customer_id" = i32, Path, description = "customer id")),
+    request_body = TransactionPayload,
+    responses(
+        (status = 200, body = CreateTransactionResponse),
+        (status = 404, description = "unknown customer"),
+        (status = 422, description = "invalid payload, insufficient limit or a spending rule violation", body = RuleViolationResponse),
+        (status = 500, description = "database failure"),
+    ),
+)]
 #[post("/clientes/{customer_id}/transacoes")]
 async fn create_transaction(
     customer_url: Path<CustomerURL>,
     payload: Json<TransactionPayload>,
     db: Data<Database>,
 ) -> HttpResponse {
-    let is_valid = payload.validate();
-    if is_valid.is_err() {
-        return HttpResponse::UnprocessableEntity().into()
-    }
-
-    let customer_id = customer_url.customer_id;
-
-    if customer_id < 0 {
-        return HttpResponse::NotFound().into()
-    }
-
-    let time_now = Utc::now();
-
-    let customer_lean = db.create_transaction(
-        payload.to_model(customer_id as i64, time_now),
+    let customer_lean = handlers::submit_transaction(
+        &db,
+        customer_url.customer_id,
+        &payload,
     ).await;
 
     match customer_lean {
         Ok(response) => {
-            HttpResponse::Ok().json(CreateTransactionResponse::from_model(&response))
+            let mut http_response = HttpResponse::Ok();
+            handlers::append_lsn(&mut http_response, &db).await;
+            http_response.json(CreateTransactionResponse::from_model(&response))
         }
         Err(err) => {
             match err {
                 Error::NotFound => {HttpResponse::NotFound().into()}
-                Error::Default => {HttpResponse::UnprocessableEntity().into()}
+                Error::Default => {HttpResponse::InternalServerError().into()}
+                Error::InsufficientLimit => {HttpResponse::UnprocessableEntity().into()}
+                Error::Invalid => {HttpResponse::UnprocessableEntity().into()}
+                Error::RuleViolation(rule) => {
+                    HttpResponse::UnprocessableEntity().json(RuleViolationResponse::from_rule(rule))
+                }
             }
         }
     }
 }
 
+#[utoipa::path(
+    tag = "extrato",
+    params(("customer_id" = i32, Path, description = "customer id"), StatementQuery),
+    responses(
+        (status = 200, description = "latest transactions; a `GetPeriodStatementResponse` when `de` and `ate` are set", content(
+            ("application/json" = GetStatementResponse),
+            ("text/csv" = String),
+            ("application/x-ofx" = String),
+            ("text/plain" = String),
+        )),
+        (status = 404, description = "unknown customer"),
+        (status = 422, description = "invalid period"),
+        (status = 501, description = "period statements are not kept by this storage backend"),
+    ),
+)]
 #[get("/clientes/{customer_id}/extrato")]
 async fn get_statement(
+    req: HttpRequest,
     customer_url: Path<CustomerURL>,
+    query: Query<StatementQuery>,
     db: Data<Database>,
 ) -> HttpResponse {
+    let is_valid = query.validate();
+    if is_valid.is_err() {
+        return HttpResponse::UnprocessableEntity().into()
+    }
+
     let customer_id = customer_url.customer_id;
 
     if customer_id < 0 {
         return HttpResponse::NotFound().into()
     }
 
-    let customer_opt = db.get_customer_by_id(customer_id).await;
+    let format = StatementFormat::negotiate(&req);
+
+    if let Some(period) = query.to_period() {
+        return match db.get_period_statement(customer_id, period).await {
+            Ok(statement) if format != StatementFormat::Json => {
+                HttpResponse::Ok()
+                    .content_type(format.content_type())
+                    .body(StatementExport::from_period(customer_id, &statement).render(format))
+            }
+            Ok(statement) => {
+                HttpResponse::Ok().json(GetPeriodStatementResponse::from_model(&statement))
+            }
+            Err(Error::NotFound) => HttpResponse::NotFound().into(),
+            // period statements are only kept by the postgres backend
+            Err(Error::Invalid) => HttpResponse::NotImplemented().into(),
+            Err(_) => HttpResponse::InternalServerError().into(),
+        }
+    }
+
+    let after = handlers::read_after(&req);
+
+    // the body is rendered straight from the latest_transactions text; this
+    // takes the place of the statement cache, which holds typed responses
+    if db.statement_raw_json && format == StatementFormat::Json && db.is_postgres() {
+        return match db.get_statement_json(customer_id, after).await {
+            Ok(body) => HttpResponse::Ok().content_type("application/json").body(body),
+            Err(Error::NotFound) => HttpResponse::NotFound().into(),
+            Err(_) => HttpResponse::InternalServerError().into(),
+        }
+    }
+
+    // a client waiting for its own write skips the cache, which may not have
+    // seen a write made through another instance yet
+    let version = if format == StatementFormat::Json && after.is_none() {
+        match db.statements.get(customer_id) {
+            Ok(response) => return HttpResponse::Ok().json(response),
+            Err(version) => Some(version),
+        }
+    } else {
+        None
+    };
+
+    let customer_opt = match version {
+        Some(_) => db.get_customer_from_primary(customer_id).await,
+        None => db.get_customer_by_id(customer_id, after).await,
+    };
     if customer_opt.is_err() {
         return HttpResponse::NotFound().into()
     }
 
     let customer = customer_opt.unwrap();
 
-    HttpResponse::Ok().json(GetStatementResponse::from_customer(&customer))
+    if format != StatementFormat::Json {
+        return HttpResponse::Ok()
+            .content_type(format.content_type())
+            .body(StatementExport::from_customer(customer_id, &customer).render(format))
+    }
+
+    let response = GetStatementResponse::from_customer(&customer);
+    if let Some(version) = version {
+        db.statements.put(customer_id, version, &response);
+    }
+
+    HttpResponse::Ok().json(response)
+}
+
+// nilapi rebalance <customer_id> <shard>
+async fn rebalance(db: &Database, args: &[String]) -> std::io::Result<()> {
+    let customer_id = args.first().and_then(|arg| arg.parse::<i32>().ok());
+    let shard = args.get(1).and_then(|arg| arg.parse::<usize>().ok());
+
+    let (Some(customer_id), Some(shard)) = (customer_id, shard) else {
+        eprintln!("usage: nilapi rebalance <customer_id> <shard>");
+        std::process::exit(2);
+    };
+
+    match db.rebalance(customer_id, shard).await {
+        Ok(moved) => {
+            println!(
+                "moved customer {} with {} transactions to shard {}; add {}:{} to DB_SHARD_MAP and restart every instance",
+                customer_id, moved, shard, customer_id, shard,
+            );
+            println!("until an instance restarts with the new map it answers 404 for customer {}", customer_id);
+            Ok(())
+        }
+        Err(err) => {
+            eprintln!("rebalance failed: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+// served by every storage backend
+fn routes(cfg: &mut ServiceConfig) {
+    cfg
+        .service(create_transaction)
+        .service(get_statement)
+        .service(handlers::simulate_fees)
+        .service(handlers::get_cache_stats)
+        .service(handlers::get_openapi)
+        .service(handlers::get_docs)
+        .service(handlers::get_ready);
+}
+
+// features whose state only exists in postgres
+fn postgres_routes(cfg: &mut ServiceConfig) {
+    cfg
+        .service(handlers::create_schedule)
+        .service(handlers::get_history)
+        .service(handlers::get_spending_rules)
+        .service(handlers::save_spending_rules)
+        .service(handlers::save_webhook)
+        .service(handlers::delete_webhook)
+        .service(handlers::get_dead_letters)
+        .service(handlers::replay_dead_letters)
+        .service(handlers::stream_events)
+        .service(handlers::create_api_key)
+        .service(handlers::transactions_socket)
+        .service(handlers::create_transactions_batch);
+}
+
+// v2 answers every failure, extractor errors included, with an ErrorResponseV2
+fn v2_routes(postgres: bool) -> Scope {
+    let v2 = scope("/v2")
+        .app_data(JsonConfig::default().error_handler(handlers::reject_request))
+        .app_data(PathConfig::default().error_handler(handlers::reject_request))
+        .app_data(QueryConfig::default().error_handler(handlers::reject_request))
+        .service(handlers::create_transaction_v2)
+        .service(handlers::get_statement_v2);
+
+    if postgres { v2.service(handlers::get_history_v2) } else { v2 }
 }
 
 #[actix_web::main]
@@ -87,13 +251,49 @@ async fn main() -> std::io::Result<()> {
 
     let server_url = &config.server_url;
 
+    let args = std::env::args().collect::<Vec<String>>();
+    if args.get(1).map(String::as_str) == Some("proxy") {
+        return proxy::run(&config).await
+    }
+
     let db = Database::init(&config).await.unwrap();
 
+    if args.get(1).map(String::as_str) == Some("rebalance") {
+        return rebalance(&db, &args[2..]).await
+    }
+
+    let postgres = db.is_postgres();
+
+    let listener = if postgres {
+        jobs::scheduler::spawn(db.clone(), &config);
+        jobs::interest::spawn(db.clone(), &config);
+        jobs::webhook::spawn(db.clone(), &config);
+
+        Listener::spawn(&config)
+    } else {
+        Listener::idle()
+    };
+
+    grpc::spawn(db.clone(), &config);
+
+    let admin_token = handlers::AdminToken::from_config(&config);
+    let webhook_targets = jobs::webhook::WebhookTargets::from_config(&config);
+    let compression = compression::Compression::from_config(&config);
+    db.statements.listen(&listener);
 
     let server = HttpServer::new(move || App::new()
-        .service(create_transaction)
-        .service(get_statement)
+        .configure(routes)
+        .configure(|cfg| if postgres { postgres_routes(cfg) })
+        .service(v2_routes(postgres))
+        .wrap_fn(move |req, srv| {
+            let encoding = compression.negotiate(&req);
+            let response = srv.call(req);
+            async move { Ok(compression.encode(response.await?, encoding)) }
+        })
         .app_data(Data::new(db.clone()))
+        .app_data(Data::new(admin_token.clone()))
+        .app_data(Data::new(webhook_targets))
+        .app_data(Data::new(listener.clone()))
         .app_data(
             JsonConfig::default().error_handler(|err, _| {
                 let e = format!("{:?}", err);
@@ -106,12 +306,37 @@ async fn main() -> std::io::Result<()> {
                 InternalError::from_response(err, HttpResponse::UnprocessableEntity().body(e)).into()
             })
         )
-    )
-        .bind(server_url)?
+        .app_data(
+            QueryConfig::default().error_handler(|err, _| {
+                let e = format!("{:?}", err);
+                InternalError::from_response(err, HttpResponse::UnprocessableEntity().body(e)).into()
+            })
+        )
+    );
+
+    // unix sockets stay on HTTP/1.1; on TCP, SERVER_HTTP2 also accepts h2c
+    // from clients that start with the HTTP/2 preface
+    let server = match (unix_socket::path(server_url), tls::server_config(&config)?) {
+        (Some(path), _) => {
+            unix_socket::remove_stale(path)?;
+            let server = server.bind_uds(path)?;
+            unix_socket::set_mode(path, &config.server_socket_mode)?;
+            server
+        }
+        (None, Some(tls_config)) => server.bind_rustls_0_23(server_url, tls_config)?,
+        (None, None) if config.server_http2 => server.bind_auto_h2c(server_url)?,
+        (None, None) => server.bind(server_url)?,
+    }
         .workers(4)
         .run();
 
     println!("listening on {}", server_url);
 
-    server.await
+    let result = server.await;
+
+    if let Some(path) = unix_socket::path(server_url) {
+        let _ = std::fs::remove_file(path);
+    }
+
+    result
 }
@@ -1,3 +1,13 @@
 pub mod transaction;
+pub mod schedule;
+pub mod statement;
+pub mod spending_rule;
+pub mod fee;
+pub mod event;
 
-pub use transaction::{Transaction, CustomerURL, TransactionCache};
\ No newline at end of file
+pub use transaction::{Transaction, CustomerURL, TransactionCache};
+pub use schedule::{Schedule, Recurrence};
+pub use statement::{StatementPeriod, PeriodStatement};
+pub use spending_rule::{SpendingRule, SpendingRules};
+pub use fee::{FeePolicy, FeeSimulation};
+pub use event::{TransactionEvent, TransactionNotification, OutboxEvent, DeadLetter, Webhook};
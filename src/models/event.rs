@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+
+use crate::models::Transaction;
+use crate::models::transaction::CustomerLean;
+use crate::serializers::rinha_date_format;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct TransactionEvent {
+    pub customer_id: i64,
+    pub amount: i64,
+    pub transaction_type: String,
+    pub description: String,
+    #[serde(with = "rinha_date_format")]
+    pub created_at: DateTime<Utc>,
+    pub limit: i64,
+    pub balance: i64,
+}
+
+impl TransactionEvent {
+    pub fn from_transaction(transaction: &Transaction, customer: &CustomerLean) -> TransactionEvent {
+        TransactionEvent{
+            customer_id: transaction.customer_id,
+            amount: transaction.amount,
+            transaction_type: transaction.transaction_type.clone(),
+            description: transaction.description.clone(),
+            created_at: transaction.created_at,
+            limit: customer.limit,
+            balance: customer.balance,
+        }
+    }
+}
+
+// broadcast on NOTIFY, `seq` orders events per customer and is the SSE event id
+#[derive(Deserialize, Serialize, Clone)]
+pub struct TransactionNotification {
+    pub seq: i64,
+    pub event: TransactionEvent,
+}
+
+impl From<Row> for TransactionNotification {
+    fn from(row: Row) -> Self {
+        Self {
+            seq: row.get("seq"),
+            event: TransactionEvent{
+                customer_id: row.get::<_, i32>("customer_id") as i64,
+                amount: row.get("amount"),
+                transaction_type: row.get("transaction_type"),
+                description: row.get("description"),
+                created_at: row.get("created_at"),
+                limit: row.get("credit_limit"),
+                balance: row.get::<_, Option<i64>>("balance").unwrap_or_default(),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct OutboxEvent {
+    pub shard: usize,
+    pub id: i64,
+    pub customer_id: i32,
+    pub event: TransactionEvent,
+    pub attempts: i32,
+    pub url: Option<String>,
+    pub secret: Option<String>,
+}
+
+impl From<Row> for OutboxEvent {
+    fn from(row: Row) -> Self {
+        Self {
+            shard: 0,
+            id: row.get("id"),
+            customer_id: row.get("customer_id"),
+            event: serde_json::from_value(row.get("payload")).unwrap(),
+            attempts: row.get("attempts"),
+            url: row.get("url"),
+            secret: row.get("secret"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct DeadLetter {
+    pub shard: usize,
+    pub id: i64,
+    pub event_id: i64,
+    pub customer_id: i32,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl From<Row> for DeadLetter {
+    fn from(row: Row) -> Self {
+        Self {
+            shard: 0,
+            id: row.get("id"),
+            event_id: row.get("event_id"),
+            customer_id: row.get("customer_id"),
+            attempts: row.get("attempts"),
+            last_error: row.get("last_error"),
+            failed_at: row.get("failed_at"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Webhook {
+    pub url: String,
+    pub secret: String,
+}
@@ -0,0 +1,214 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::Transaction;
+use crate::models::transaction::{TRANSACTION_DEBIT, TRANSACTION_FEE, TRANSACTION_INTEREST};
+
+const BPS_DENOMINATOR: i64 = 10_000;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default)]
+pub struct FeePolicy {
+    pub daily_interest_bps: i64,
+    pub debit_fee: i64,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SimulatedDay {
+    pub day: i64,
+    pub interest: i64,
+    pub balance: i64,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct FeeSimulation {
+    pub starting_balance: i64,
+    pub limit: i64,
+    pub total_interest: i64,
+    pub total_fees: i64,
+    pub final_balance: i64,
+    pub exceeds_limit: bool,
+    pub days: Vec<SimulatedDay>,
+}
+
+impl FeePolicy {
+    pub fn from_config(config: &Config) -> FeePolicy {
+        FeePolicy{
+            daily_interest_bps: config.fees_daily_interest_bps,
+            debit_fee: config.fees_debit_fee,
+        }
+    }
+
+    // interest is charged on the negative part of the balance only, rounded up
+    // to the next cent so that tiny overdrafts are never free
+    pub fn daily_interest(&self, balance: i64) -> i64 {
+        if balance >= 0 || self.daily_interest_bps <= 0 {
+            return 0
+        }
+
+        let owed = -balance * self.daily_interest_bps;
+        (owed + BPS_DENOMINATOR - 1) / BPS_DENOMINATOR
+    }
+
+    pub fn debit_fee_for(&self, transaction: &Transaction) -> Option<Transaction> {
+        if self.debit_fee <= 0 || transaction.transaction_type != TRANSACTION_DEBIT {
+            return None
+        }
+
+        Some(Transaction{
+            id: Uuid::new_v4(),
+            customer_id: transaction.customer_id,
+            amount: self.debit_fee,
+            transaction_type: TRANSACTION_FEE.to_string(),
+            description: String::from("tarifa"),
+            category: None,
+            tags: vec![],
+            metadata: None,
+            created_at: transaction.created_at,
+        })
+    }
+
+    pub fn interest_for(&self, customer_id: i64, balance: i64, created_at: DateTime<Utc>) -> Option<Transaction> {
+        let interest = self.daily_interest(balance);
+        if interest == 0 {
+            return None
+        }
+
+        Some(interest_entry(customer_id, interest, created_at))
+    }
+
+    // Deterministic preview: `debits` fee-bearing debits are charged up front,
+    // then interest compounds daily on the resulting balance.
+    pub fn simulate(&self, balance: i64, limit: i64, days: i64, debits: i64) -> FeeSimulation {
+        let total_fees = if self.debit_fee > 0 { self.debit_fee * debits } else { 0 };
+
+        let mut current = balance - total_fees;
+        let mut total_interest = 0;
+        let mut simulated_days = Vec::with_capacity(days as usize);
+
+        for day in 1..=days {
+            let interest = self.daily_interest(current);
+            current -= interest;
+            total_interest += interest;
+
+            simulated_days.push(SimulatedDay{
+                day,
+                interest,
+                balance: current,
+            });
+        }
+
+        FeeSimulation{
+            starting_balance: balance,
+            limit,
+            total_interest,
+            total_fees,
+            final_balance: current,
+            exceeds_limit: current < -limit,
+            days: simulated_days,
+        }
+    }
+}
+
+pub fn interest_entry(customer_id: i64, amount: i64, created_at: DateTime<Utc>) -> Transaction {
+    Transaction{
+        id: Uuid::new_v4(),
+        customer_id,
+        amount,
+        transaction_type: TRANSACTION_INTEREST.to_string(),
+        description: String::from("juros"),
+        category: None,
+        tags: vec![],
+        metadata: None,
+        created_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::models::transaction::{TRANSACTION_CREDIT, TRANSACTION_DEBIT, TRANSACTION_FEE};
+    use super::*;
+
+    fn policy(daily_interest_bps: i64, debit_fee: i64) -> FeePolicy {
+        FeePolicy{ daily_interest_bps, debit_fee }
+    }
+
+    fn transaction(transaction_type: &str) -> Transaction {
+        Transaction{
+            id: Uuid::new_v4(),
+            customer_id: 1,
+            amount: 1000,
+            transaction_type: transaction_type.to_string(),
+            description: String::from("compra"),
+            category: None,
+            tags: vec![],
+            metadata: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn interest_is_only_charged_on_negative_balances() {
+        assert_eq!(policy(100, 0).daily_interest(0), 0);
+        assert_eq!(policy(100, 0).daily_interest(5000), 0);
+        assert_eq!(policy(0, 0).daily_interest(-5000), 0);
+        assert_eq!(policy(100, 0).daily_interest(-5000), 50);
+    }
+
+    #[test]
+    fn interest_rounds_up_to_the_next_cent() {
+        assert_eq!(policy(100, 0).daily_interest(-1), 1);
+        assert_eq!(policy(100, 0).daily_interest(-101), 2);
+        assert_eq!(policy(33, 0).daily_interest(-10_000), 33);
+    }
+
+    #[test]
+    fn fees_are_only_charged_on_debits() {
+        let fee = policy(0, 150).debit_fee_for(&transaction(TRANSACTION_DEBIT)).unwrap();
+        assert_eq!(fee.amount, 150);
+        assert_eq!(fee.transaction_type, TRANSACTION_FEE);
+
+        assert!(policy(0, 150).debit_fee_for(&transaction(TRANSACTION_CREDIT)).is_none());
+        assert!(policy(0, 0).debit_fee_for(&transaction(TRANSACTION_DEBIT)).is_none());
+    }
+
+    #[test]
+    fn simulation_charges_fees_then_compounds_interest() {
+        let simulation = policy(100, 50).simulate(-10_000, 20_000, 3, 2);
+
+        assert_eq!(simulation.total_fees, 100);
+        // -10100 -> 101 -> -10201 -> 103 -> -10304 -> 104 -> -10408
+        assert_eq!(
+            simulation.days.iter().map(|day| (day.day, day.interest, day.balance)).collect::<Vec<_>>(),
+            vec![(1, 101, -10_201), (2, 103, -10_304), (3, 104, -10_408)],
+        );
+        assert_eq!(simulation.total_interest, 308);
+        assert_eq!(simulation.final_balance, -10_408);
+        assert!(!simulation.exceeds_limit);
+    }
+
+    #[test]
+    fn simulation_reports_a_balance_past_the_limit() {
+        let simulation = policy(1000, 0).simulate(-900, 1000, 2, 0);
+
+        assert_eq!(simulation.final_balance, -1089);
+        assert!(simulation.exceeds_limit);
+    }
+
+    #[test]
+    fn simulation_is_deterministic_and_idle_on_positive_balances() {
+        let policy = policy(100, 0);
+
+        assert_eq!(
+            serde_json::to_string(&policy.simulate(-7777, 10_000, 30, 3)).unwrap(),
+            serde_json::to_string(&policy.simulate(-7777, 10_000, 30, 3)).unwrap(),
+        );
+
+        let simulation = policy.simulate(500, 10_000, 5, 0);
+        assert_eq!(simulation.total_interest, 0);
+        assert_eq!(simulation.final_balance, 500);
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use uuid::{Builder, Uuid};
+use utoipa::ToSchema;
+
+use crate::models::Transaction;
+use crate::serializers::rinha_date_format;
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Default, ToSchema)]
+pub enum Recurrence {
+    #[default]
+    #[serde(rename = "unica")]
+    Once,
+    #[serde(rename = "diaria")]
+    Daily,
+    #[serde(rename = "semanal")]
+    Weekly,
+    #[serde(rename = "mensal")]
+    Monthly,
+}
+
+impl Recurrence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Recurrence::Once => "once",
+            Recurrence::Daily => "daily",
+            Recurrence::Weekly => "weekly",
+            Recurrence::Monthly => "monthly",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Recurrence {
+        match value {
+            "daily" => Recurrence::Daily,
+            "weekly" => Recurrence::Weekly,
+            "monthly" => Recurrence::Monthly,
+            _ => Recurrence::Once,
+        }
+    }
+
+    // next occurrence after `from`, None when the schedule is exhausted. Months
+    // are counted from the first run so a day clamped to the end of a short
+    // month goes back to the original day afterwards.
+    pub fn next_run(&self, anchor: DateTime<Utc>, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Recurrence::Once => None,
+            Recurrence::Daily => Some(from + Duration::days(1)),
+            Recurrence::Weekly => Some(from + Duration::weeks(1)),
+            Recurrence::Monthly => {
+                let months = (from.year() - anchor.year()) * 12 + from.month() as i32 - anchor.month() as i32;
+                anchor.checked_add_months(Months::new(months as u32 + 1))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Schedule {
+    pub id: i64,
+    pub customer_id: i32,
+    pub amount: i64,
+    pub transaction_type: String,
+    pub description: String,
+    pub recurrence: Recurrence,
+    #[serde(with = "rinha_date_format")]
+    pub next_run_at: DateTime<Utc>,
+    #[serde(with = "rinha_date_format")]
+    pub anchor_at: DateTime<Utc>,
+    pub attempts: i32,
+}
+
+impl Schedule {
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        self.recurrence.next_run(self.anchor_at, self.next_run_at)
+    }
+
+    // the same for every attempt at an occurrence, see Database::post_scheduled
+    fn occurrence_id(&self) -> Uuid {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.id.to_be_bytes());
+        bytes[8..].copy_from_slice(&self.next_run_at.timestamp_micros().to_be_bytes());
+        Builder::from_custom_bytes(bytes).into_uuid()
+    }
+
+    pub fn to_transaction(&self, created_at: DateTime<Utc>) -> Transaction {
+        Transaction{
+            id: self.occurrence_id(),
+            customer_id: self.customer_id as i64,
+            amount: self.amount,
+            transaction_type: self.transaction_type.clone(),
+            description: self.description.clone(),
+            category: None,
+            tags: vec![],
+            metadata: None,
+            created_at,
+        }
+    }
+}
+
+impl From<Row> for Schedule {
+    fn from(row: Row) -> Self {
+        let recurrence: String = row.get("recurrence");
+
+        Self {
+            id: row.get("id"),
+            customer_id: row.get("customer_id"),
+            amount: row.get("amount"),
+            transaction_type: row.get("transaction_type"),
+            description: row.get("description"),
+            recurrence: Recurrence::from_db(&recurrence),
+            next_run_at: row.get("next_run_at"),
+            anchor_at: row.get("anchor_at"),
+            attempts: row.get("attempts"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::Recurrence;
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 9, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn monthly_clamps_to_month_end_and_returns_to_the_anchor_day() {
+        let anchor = at(2026, 1, 31);
+
+        let february = Recurrence::Monthly.next_run(anchor, anchor).unwrap();
+        assert_eq!(february, at(2026, 2, 28));
+
+        let march = Recurrence::Monthly.next_run(anchor, february).unwrap();
+        assert_eq!(march, at(2026, 3, 31));
+
+        let april = Recurrence::Monthly.next_run(anchor, march).unwrap();
+        assert_eq!(april, at(2026, 4, 30));
+    }
+
+    #[test]
+    fn monthly_lands_on_the_leap_day() {
+        let anchor = at(2028, 1, 31);
+
+        let february = Recurrence::Monthly.next_run(anchor, anchor).unwrap();
+        assert_eq!(february, at(2028, 2, 29));
+        assert_eq!(Recurrence::Monthly.next_run(anchor, february), Some(at(2028, 3, 31)));
+    }
+
+    #[test]
+    fn monthly_crosses_the_year() {
+        let anchor = at(2026, 11, 30);
+
+        let december = Recurrence::Monthly.next_run(anchor, anchor).unwrap();
+        assert_eq!(december, at(2026, 12, 30));
+        assert_eq!(Recurrence::Monthly.next_run(anchor, december), Some(at(2027, 1, 30)));
+    }
+
+    #[test]
+    fn daily_and_weekly_step_from_the_last_run() {
+        let anchor = at(2026, 2, 27);
+
+        assert_eq!(Recurrence::Daily.next_run(anchor, anchor), Some(at(2026, 2, 28)));
+        assert_eq!(Recurrence::Daily.next_run(anchor, at(2026, 2, 28)), Some(at(2026, 3, 1)));
+        assert_eq!(Recurrence::Weekly.next_run(anchor, anchor), Some(at(2026, 3, 6)));
+        assert_eq!(Recurrence::Weekly.next_run(anchor, at(2026, 12, 28)), Some(at(2027, 1, 4)));
+    }
+
+    #[test]
+    fn once_has_no_next_run() {
+        let anchor = at(2026, 1, 31);
+
+        assert_eq!(Recurrence::Once.next_run(anchor, anchor), None);
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpendingRule {
+    MaxDebit,
+    MaxDailyDebit,
+    MaxHourlyDebits,
+    BlockedDescription,
+}
+
+impl SpendingRule {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SpendingRule::MaxDebit => "limite_debito_unico",
+            SpendingRule::MaxDailyDebit => "limite_debito_diario",
+            SpendingRule::MaxHourlyDebits => "limite_debitos_por_hora",
+            SpendingRule::BlockedDescription => "descricao_bloqueada",
+        }
+    }
+
+    pub fn code_v2(&self) -> &'static str {
+        match self {
+            SpendingRule::MaxDebit => "max_debit_exceeded",
+            SpendingRule::MaxDailyDebit => "max_daily_debit_exceeded",
+            SpendingRule::MaxHourlyDebits => "max_hourly_debits_exceeded",
+            SpendingRule::BlockedDescription => "blocked_description",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<SpendingRule> {
+        [
+            SpendingRule::MaxDebit,
+            SpendingRule::MaxDailyDebit,
+            SpendingRule::MaxHourlyDebits,
+            SpendingRule::BlockedDescription,
+        ].into_iter().find(|rule| rule.code() == code)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct SpendingRules {
+    pub max_debit: Option<i64>,
+    pub max_daily_debit: Option<i64>,
+    pub max_hourly_debits: Option<i32>,
+    pub blocked_patterns: Vec<String>,
+}
+
+impl From<Row> for SpendingRules {
+    fn from(row: Row) -> Self {
+        Self {
+            max_debit: row.get("max_debit"),
+            max_daily_debit: row.get("max_daily_debit"),
+            max_hourly_debits: row.get("max_hourly_debits"),
+            blocked_patterns: row.get("blocked_patterns"),
+        }
+    }
+}
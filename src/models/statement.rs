@@ -0,0 +1,33 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::TransactionCache;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct StatementPeriod {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl StatementPeriod {
+    // the period is inclusive on both dates, in UTC; StatementQuery keeps
+    // `end` well before the last date chrono can represent
+    pub fn starts_at(&self) -> DateTime<Utc> {
+        self.start.and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+
+    pub fn ends_at(&self) -> DateTime<Utc> {
+        self.end.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct PeriodStatement {
+    pub period: StatementPeriod,
+    pub limit: i64,
+    pub opening_balance: i64,
+    pub closing_balance: i64,
+    pub total_credits: i64,
+    pub total_debits: i64,
+    pub transactions: Vec<TransactionCache>,
+}
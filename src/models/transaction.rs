@@ -1,25 +1,42 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tokio_postgres::Row;
+use uuid::Uuid;
 use validator::{Validate};
 
 use crate::serializers::rinha_date_format;
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Transaction {
+    // minted once, so the ledger row, the cache entry and exports agree on it
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
     pub customer_id: i64,
     pub amount: i64,
     pub transaction_type: String,
     pub description: String,
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub metadata: Option<serde_json::Value>,
     #[serde(with = "rinha_date_format")]
     pub created_at: DateTime<Utc>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct TransactionCache {
+    // entries cached before ids were kept have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
     pub amount: i64,
     pub transaction_type: String,
     pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
     #[serde(with = "rinha_date_format")]
     pub created_at: DateTime<Utc>,
 }
@@ -36,31 +53,75 @@ pub struct Customer {
     pub transactions: Vec<TransactionCache>
 }
 
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct TransactionFilter {
+    pub category: Option<String>,
+    pub tag: Option<String>,
+    pub metadata_key: Option<String>,
+    pub metadata_value: Option<String>,
+    pub limit: i64,
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct CustomerLean{
     pub limit: i64,
     pub balance: i64,
 }
 
+pub const TRANSACTION_CREDIT: &str = "c";
+pub const TRANSACTION_DEBIT: &str = "d";
+pub const TRANSACTION_INTEREST: &str = "j";
+pub const TRANSACTION_FEE: &str = "t";
+
+impl Transaction {
+    // only credits add to the balance, debits, interest and fees all subtract
+    pub fn signed_amount(&self) -> i64 {
+        if self.transaction_type == TRANSACTION_CREDIT {
+            return self.amount
+        }
+
+        -self.amount
+    }
+}
+
 impl TransactionCache {
     pub fn from_transaction(transaction: &Transaction) -> TransactionCache {
         TransactionCache{
+            id: Some(transaction.id),
             amount: transaction.amount,
             transaction_type: transaction.transaction_type.clone(),
             description: transaction.description.clone(),
+            category: transaction.category.clone(),
+            tags: transaction.tags.clone(),
+            metadata: transaction.metadata.clone(),
             created_at: transaction.created_at,
         }
     }
 }
 
+impl From<Row> for TransactionCache {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.try_get("id").ok(),
+            amount: row.get("amount"),
+            transaction_type: row.get("transaction_type"),
+            description: row.get("description"),
+            category: row.get("category"),
+            tags: row.get("tags"),
+            metadata: row.get("metadata"),
+            created_at: row.get("created_at"),
+        }
+    }
+}
+
 impl From<Row> for Customer {
     fn from(row: Row) -> Self {
         let latest_transactions: Option<serde_json::Value> = row.get("latest_transactions");
 
         let mut transactions = vec![];
 
-        if latest_transactions.is_some() {
-            transactions = serde_json::from_value(latest_transactions.unwrap()).unwrap();
+        if let Some(latest_transactions) = latest_transactions {
+            transactions = serde_json::from_value(latest_transactions).unwrap();
         }
 
         Self {
@@ -0,0 +1,228 @@
+use utoipa::OpenApi;
+
+use crate::handlers::{batch, event, fee, history, ready, schedule, spending_rule, v2, webhook};
+use crate::models::Recurrence;
+use crate::requests::{
+    BatchMode, SchedulePayload, SpendingRulesPayload, TransactionPayload, TransactionPayloadV2,
+    WebhookPayload,
+};
+use crate::responses::{
+    BatchFailureResponse, BatchItemResponse, BatchResponse, CreateScheduleResponse,
+    CreateTransactionResponse, FeeSimulationResponse, SimulatedDayResponse,
+    SpendingRulesResponse, TransactionEventResponse,
+    GetHistoryResponse, GetPeriodStatementBalanceResponse, GetPeriodStatementPeriodResponse,
+    GetPeriodStatementResponse, GetPeriodStatementTotalsResponse, GetStatementBalanceResponse,
+    GetStatementResponse, GetStatementTransactionsCacheResponse, RuleViolationResponse,
+    CreateTransactionResponseV2, ErrorResponseV2, GetHistoryResponseV2, GetStatementResponseV2,
+    StatementBalanceResponseV2, TransactionResponseV2,
+};
+
+// Schemas are derived from the serde attributes of the request and response
+// types, so the document follows whatever the handlers actually serialize.
+// Left out on purpose: the /admin routes, which are operator tooling behind
+// ADMIN_TOKEN, /ws/transacoes, which OpenAPI has no way to describe, and the
+// /openapi.json and /docs pages themselves.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "nilapi"),
+    paths(
+        crate::create_transaction,
+        crate::get_statement,
+        history::get_history,
+        batch::create_transactions_batch,
+        schedule::create_schedule,
+        spending_rule::get_spending_rules,
+        spending_rule::save_spending_rules,
+        webhook::save_webhook,
+        webhook::delete_webhook,
+        fee::simulate_fees,
+        event::stream_events,
+        ready::get_ready,
+        v2::create_transaction_v2,
+        v2::get_statement_v2,
+        v2::get_history_v2,
+    ),
+    components(schemas(
+        TransactionPayload,
+        CreateTransactionResponse,
+        RuleViolationResponse,
+        GetStatementResponse,
+        GetStatementBalanceResponse,
+        GetStatementTransactionsCacheResponse,
+        GetPeriodStatementResponse,
+        GetPeriodStatementBalanceResponse,
+        GetPeriodStatementPeriodResponse,
+        GetPeriodStatementTotalsResponse,
+        GetHistoryResponse,
+        BatchMode,
+        BatchResponse,
+        BatchItemResponse,
+        BatchFailureResponse,
+        SchedulePayload,
+        Recurrence,
+        CreateScheduleResponse,
+        SpendingRulesPayload,
+        SpendingRulesResponse,
+        WebhookPayload,
+        FeeSimulationResponse,
+        SimulatedDayResponse,
+        TransactionEventResponse,
+        TransactionPayloadV2,
+        CreateTransactionResponseV2,
+        GetStatementResponseV2,
+        StatementBalanceResponseV2,
+        TransactionResponseV2,
+        GetHistoryResponseV2,
+        ErrorResponseV2,
+    )),
+)]
+pub struct ApiDoc;
+
+pub const VIEWER: &str = include_str!("openapi/viewer.html");
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+    use jsonschema::{Draft, JSONSchema};
+    use serde::Serialize;
+    use serde_json::{json, Value};
+    use utoipa::OpenApi;
+
+    use crate::errors::Error;
+    use crate::models::{FeePolicy, PeriodStatement, Recurrence, Schedule, SpendingRule, SpendingRules, StatementPeriod, TransactionCache, TransactionEvent};
+    use crate::models::transaction::{Customer, CustomerLean};
+    use crate::requests::BatchMode;
+    use crate::responses::*;
+    use super::ApiDoc;
+
+    // OpenAPI 3.0 marks optional values with `nullable`, which JSON Schema
+    // does not know; objects are closed so a field the spec lacks fails too.
+    fn to_json_schema(schema: &mut Value) {
+        match schema {
+            Value::Object(object) => {
+                object.values_mut().for_each(to_json_schema);
+
+                if object.contains_key("properties") && !object.contains_key("additionalProperties") {
+                    object.insert(String::from("additionalProperties"), Value::Bool(false));
+                }
+
+                if object.remove("nullable") == Some(Value::Bool(true)) {
+                    let inner = Value::Object(std::mem::take(object));
+                    object.insert(String::from("anyOf"), json!([{ "type": "null" }, inner]));
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(to_json_schema),
+            _ => {}
+        }
+    }
+
+    fn assert_matches_spec<T: Serialize>(name: &str, value: &T) {
+        let mut schemas = serde_json::to_value(ApiDoc::openapi()).unwrap()["components"]["schemas"].take();
+        assert!(schemas.get(name).is_some(), "{} is not in components.schemas", name);
+        to_json_schema(&mut schemas);
+
+        let schema = json!({
+            "allOf": [{ "$ref": format!("#/components/schemas/{}", name) }],
+            "components": { "schemas": schemas },
+        });
+        let validator = JSONSchema::options()
+            .with_draft(Draft::Draft7)
+            .should_validate_formats(true)
+            .compile(&schema)
+            .unwrap();
+
+        let instance = serde_json::to_value(value).unwrap();
+        let errors = match validator.validate(&instance) {
+            Ok(()) => vec![],
+            Err(errors) => errors.map(|err| format!("{} at {}", err, err.instance_path)).collect::<Vec<_>>(),
+        };
+        assert!(errors.is_empty(), "{} drifted from its schema: {:?}\n{}", name, errors, instance);
+    }
+
+    fn transactions() -> Vec<TransactionCache> {
+        vec![
+            TransactionCache{
+                id: Some(uuid::Uuid::new_v4()),
+                amount: 1000,
+                transaction_type: String::from("d"),
+                description: String::from("mercado"),
+                category: Some(String::from("food")),
+                tags: vec![String::from("casa")],
+                metadata: Some(json!({ "loja": "centro" })),
+                created_at: Utc::now(),
+            },
+            TransactionCache{
+                id: None,
+                amount: 500,
+                transaction_type: String::from("c"),
+                description: String::from("pix"),
+                category: None,
+                tags: vec![],
+                metadata: None,
+                created_at: Utc::now(),
+            },
+        ]
+    }
+
+    #[test]
+    fn responses_match_their_schemas() {
+        let customer = Customer{ limit: 100000, balance: -500, transactions: transactions() };
+        let lean = CustomerLean{ limit: 100000, balance: -500 };
+        let statement = PeriodStatement{
+            period: StatementPeriod{
+                start: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                end: NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+            },
+            limit: 100000,
+            opening_balance: 0,
+            closing_balance: -500,
+            total_credits: 500,
+            total_debits: 1000,
+            transactions: transactions(),
+        };
+        let rules = SpendingRules{
+            max_debit: Some(1000),
+            max_daily_debit: None,
+            max_hourly_debits: Some(3),
+            blocked_patterns: vec![String::from("casino")],
+        };
+        let schedule = Schedule{
+            id: 1,
+            customer_id: 1,
+            amount: 100,
+            transaction_type: String::from("d"),
+            description: String::from("aluguel"),
+            recurrence: Recurrence::Monthly,
+            next_run_at: Utc::now(),
+            anchor_at: Utc::now(),
+            attempts: 0,
+        };
+        let fees = FeePolicy{ daily_interest_bps: 10, debit_fee: 50 };
+        let event = TransactionEvent{
+            customer_id: 1,
+            amount: 1000,
+            transaction_type: String::from("d"),
+            description: String::from("mercado"),
+            created_at: Utc::now(),
+            limit: 100000,
+            balance: -500,
+        };
+        let results = vec![Ok(lean.clone()), Err(Error::InsufficientLimit)];
+
+        assert_matches_spec("CreateTransactionResponse", &CreateTransactionResponse::from_model(&lean));
+        assert_matches_spec("RuleViolationResponse", &RuleViolationResponse::from_rule(SpendingRule::MaxDebit));
+        assert_matches_spec("GetStatementResponse", &GetStatementResponse::from_customer(&customer));
+        assert_matches_spec("GetPeriodStatementResponse", &GetPeriodStatementResponse::from_model(&statement));
+        assert_matches_spec("GetHistoryResponse", &GetHistoryResponse::from_models(&transactions()));
+        assert_matches_spec("BatchResponse", &BatchResponse::from_results(BatchMode::Partial, &results));
+        assert_matches_spec("BatchFailureResponse", &BatchFailureResponse{ index: 1, error: String::from("limite_insuficiente") });
+        assert_matches_spec("CreateScheduleResponse", &CreateScheduleResponse::from_model(&schedule));
+        assert_matches_spec("SpendingRulesResponse", &SpendingRulesResponse::from_model(&rules));
+        assert_matches_spec("FeeSimulationResponse", &FeeSimulationResponse::from_model(&fees, &fees.simulate(-500, 100000, 3, 2)));
+        assert_matches_spec("TransactionEventResponse", &TransactionEventResponse::from_model(1, &event));
+        assert_matches_spec("CreateTransactionResponseV2", &CreateTransactionResponseV2::from_model(&lean));
+        assert_matches_spec("GetStatementResponseV2", &GetStatementResponseV2::from_customer(&customer));
+        assert_matches_spec("GetHistoryResponseV2", &GetHistoryResponseV2::from_models(&transactions()));
+        assert_matches_spec("ErrorResponseV2", &ErrorResponseV2::from_error(&Error::NotFound));
+    }
+}
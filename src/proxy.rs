@@ -0,0 +1,234 @@
+mod upstream;
+
+use std::time::Duration;
+
+use actix_codec::Framed;
+use actix_web::{guard, web, App, HttpRequest, HttpResponse, HttpServer};
+use actix_web::body::SizedStream;
+use actix_web::http::header::{self, HeaderName};
+use actix_web::web::{Bytes, Data, Payload, PayloadConfig};
+use actix_ws::{Message, MessageStream, Session};
+use awc::{BoxedSocket, Client};
+use awc::error::{SendRequestError, WsClientError};
+use awc::ws::{Codec, Frame};
+use futures_util::{SinkExt, StreamExt};
+
+use crate::config::Config;
+use upstream::{Active, Upstreams, client, base_url};
+
+const MAX_BODY: usize = 1024 * 1024;
+
+// headers that describe one connection and must not cross the proxy
+const HOP_BY_HOP: [HeaderName; 6] = [
+    header::CONNECTION,
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+struct Clients(Vec<Client>);
+
+// nilapi proxy: listens on SERVER_URL and spreads every request over
+// PROXY_UPSTREAMS, standing in for the nginx in front of the instances.
+pub async fn run(config: &Config) -> std::io::Result<()> {
+    if config.proxy_upstreams.is_empty() {
+        panic!("nilapi proxy needs PROXY_UPSTREAMS");
+    }
+
+    let upstreams = Upstreams::new(&config.proxy_upstreams);
+    let timeout = Duration::from_millis(config.proxy_timeout_ms);
+    upstreams.spawn_health_checks(Duration::from_millis(config.proxy_health_interval_ms), timeout);
+
+    let server = HttpServer::new(move || {
+        let clients = (0..upstreams.len())
+            .map(|index| client(upstreams.address(index), timeout))
+            .collect();
+
+        App::new()
+            .app_data(Data::new(upstreams.clone()))
+            .app_data(Data::new(Clients(clients)))
+            .app_data(PayloadConfig::new(MAX_BODY))
+            .service(web::resource("/{path:.*}")
+                .guard(guard::fn_guard(|ctx| ctx.head().upgrade()))
+                .to(forward_socket))
+            .default_service(web::to(forward))
+    })
+        .bind(&config.server_url)?
+        .workers(4)
+        .run();
+
+    println!("proxying {} to {}", config.server_url, config.proxy_upstreams.join(", "));
+
+    server.await
+}
+
+async fn forward(
+    req: HttpRequest,
+    body: Bytes,
+    upstreams: Data<Upstreams>,
+    clients: Data<Clients>,
+) -> HttpResponse {
+    let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+    let mut tried = Vec::new();
+
+    while let Some(index) = upstreams.pick(&tried) {
+        tried.push(index);
+        let active = upstreams.acquire(index);
+
+        let mut request = clients.0[index]
+            .request(req.method().clone(), format!("{}{}", base_url(upstreams.address(index)), path))
+            .no_decompress();
+
+        for (name, value) in req.headers() {
+            if !HOP_BY_HOP.contains(name) {
+                request = request.append_header((name.clone(), value.clone()));
+            }
+        }
+        if let Some(peer) = req.peer_addr() {
+            request = request.append_header(("x-forwarded-for", peer.ip().to_string()));
+        }
+
+        let response = match request.send_body(body.clone()).await {
+            Ok(response) => response,
+            // nothing reached the upstream, so even a POST is safe to retry
+            Err(SendRequestError::Connect(err)) => {
+                log::warn!("upstream {}: {}", upstreams.address(index), err);
+                upstreams.mark_unhealthy(index);
+                continue
+            }
+            Err(err) => {
+                log::warn!("upstream {}: {}", upstreams.address(index), err);
+                return HttpResponse::BadGateway().finish()
+            }
+        };
+
+        let mut builder = HttpResponse::build(response.status());
+        for (name, value) in response.headers() {
+            if !HOP_BY_HOP.contains(name) && name != header::CONTENT_LENGTH {
+                builder.append_header((name.clone(), value.clone()));
+            }
+        }
+
+        let length = response.headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok()?.parse::<u64>().ok());
+
+        // the upstream stays counted until its body is fully relayed, which
+        // keeps long lived streams like /eventos in the balance
+        let stream = response.map(move |chunk| {
+            let _ = &active;
+            chunk
+        });
+
+        return match length {
+            Some(length) => builder.body(SizedStream::new(length, stream)),
+            None => builder.streaming(stream),
+        }
+    }
+
+    HttpResponse::BadGateway().finish()
+}
+
+// An upgraded connection can't be relayed as a plain request, so the proxy
+// accepts the client's WebSocket itself, opens its own to an upstream and
+// relays the frames between the two. The handshake carries the client's
+// headers, so the upstream still sees its API key.
+async fn forward_socket(
+    req: HttpRequest,
+    body: Payload,
+    upstreams: Data<Upstreams>,
+    clients: Data<Clients>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+    let mut tried = Vec::new();
+
+    while let Some(index) = upstreams.pick(&tried) {
+        tried.push(index);
+        let active = upstreams.acquire(index);
+
+        let mut request = clients.0[index].ws(format!("{}{}", base_url(upstreams.address(index)), path));
+
+        for (name, value) in req.headers() {
+            if !HOP_BY_HOP.contains(name) && !name.as_str().starts_with("sec-websocket-") {
+                request = request.header(name.clone(), value.clone());
+            }
+        }
+        if let Some(peer) = req.peer_addr() {
+            request = request.header("x-forwarded-for", peer.ip().to_string());
+        }
+
+        let upstream = match request.connect().await {
+            Ok((_, upstream)) => upstream,
+            Err(WsClientError::SendRequest(SendRequestError::Connect(err))) => {
+                log::warn!("upstream {}: {}", upstreams.address(index), err);
+                upstreams.mark_unhealthy(index);
+                continue
+            }
+            // the upstream refused the upgrade, a 401 for a bad API key
+            Err(WsClientError::InvalidResponseStatus(status)) => {
+                return Ok(HttpResponse::build(status).finish())
+            }
+            Err(err) => {
+                log::warn!("upstream {}: {}", upstreams.address(index), err);
+                return Ok(HttpResponse::BadGateway().finish())
+            }
+        };
+
+        let (response, session, messages) = actix_ws::handle(&req, body)?;
+        actix_web::rt::spawn(relay(session, messages, upstream, active));
+
+        return Ok(response)
+    }
+
+    Ok(HttpResponse::BadGateway().finish())
+}
+
+async fn relay(
+    mut session: Session,
+    mut messages: MessageStream,
+    upstream: Framed<BoxedSocket, Codec>,
+    active: Active,
+) {
+    let (mut sink, mut frames) = upstream.split();
+
+    loop {
+        tokio::select! {
+            message = messages.next() => {
+                let Some(Ok(message)) = message else {
+                    break
+                };
+
+                if sink.send(message).await.is_err() {
+                    break
+                }
+            }
+            frame = frames.next() => {
+                let result = match frame {
+                    Some(Ok(Frame::Text(text))) => match String::from_utf8(text.to_vec()) {
+                        Ok(text) => session.text(text).await,
+                        Err(_) => break,
+                    },
+                    Some(Ok(Frame::Binary(bytes))) => session.binary(bytes).await,
+                    Some(Ok(Frame::Continuation(item))) => session.continuation(item).await,
+                    Some(Ok(Frame::Ping(bytes))) => session.ping(&bytes).await,
+                    Some(Ok(Frame::Pong(bytes))) => session.pong(&bytes).await,
+                    Some(Ok(Frame::Close(reason))) => {
+                        let _ = session.close(reason).await;
+                        return
+                    }
+                    _ => break,
+                };
+
+                if result.is_err() {
+                    break
+                }
+            }
+        }
+    }
+
+    let _ = sink.send(Message::Close(None)).await;
+    let _ = session.close(None).await;
+    drop(active);
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use actix_service::fn_service;
+use actix_tls::connect::{ConnectError, ConnectInfo, Connection};
+use actix_web::http::Uri;
+use actix_web::rt::net::UnixStream;
+use awc::{Client, Connector};
+
+use crate::unix_socket;
+
+struct Upstream {
+    address: String,
+    healthy: AtomicBool,
+    active: AtomicUsize,
+}
+
+// The instances behind the proxy, shared by every worker. Health comes from
+// polling /ready; a connect error also takes an upstream out until the next
+// check brings it back.
+#[derive(Clone)]
+pub struct Upstreams {
+    inner: Arc<Vec<Upstream>>,
+    next: Arc<AtomicUsize>,
+}
+
+// Counts a request against its upstream for as long as it is alive.
+pub struct Active {
+    upstreams: Upstreams,
+    index: usize,
+}
+
+impl Upstreams {
+    pub fn new(addresses: &[String]) -> Upstreams {
+        let upstreams = addresses.iter()
+            .map(|address| Upstream{
+                address: address.clone(),
+                healthy: AtomicBool::new(true),
+                active: AtomicUsize::new(0),
+            })
+            .collect();
+
+        Upstreams{ inner: Arc::new(upstreams), next: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn address(&self, index: usize) -> &str {
+        &self.inner[index].address
+    }
+
+    // Least connections among the healthy upstreams not tried yet, ties
+    // broken round robin. With none healthy left the others are still tried
+    // rather than failing outright.
+    pub fn pick(&self, tried: &[usize]) -> Option<usize> {
+        let count = self.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+
+        let candidates = (0..count)
+            .map(|offset| (start + offset) % count)
+            .filter(|index| !tried.contains(index));
+
+        let least_active = |only_healthy: bool| candidates.clone()
+            .filter(|index| !only_healthy || self.inner[*index].healthy.load(Ordering::Relaxed))
+            .min_by_key(|index| self.inner[*index].active.load(Ordering::Relaxed));
+
+        least_active(true).or_else(|| least_active(false))
+    }
+
+    pub fn acquire(&self, index: usize) -> Active {
+        self.inner[index].active.fetch_add(1, Ordering::Relaxed);
+        Active{ upstreams: self.clone(), index }
+    }
+
+    pub fn mark_unhealthy(&self, index: usize) {
+        if self.inner[index].healthy.swap(false, Ordering::Relaxed) {
+            log::warn!("upstream {} is down", self.address(index));
+        }
+    }
+
+    pub fn spawn_health_checks(&self, interval: Duration, timeout: Duration) {
+        let upstreams = self.clone();
+
+        actix_web::rt::spawn(async move {
+            let clients = (0..upstreams.len())
+                .map(|index| client(upstreams.address(index), timeout))
+                .collect::<Vec<Client>>();
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+
+                for (index, upstream) in upstreams.inner.iter().enumerate() {
+                    let response = clients[index]
+                        .get(format!("{}/ready", base_url(&upstream.address)))
+                        .send()
+                        .await;
+                    let healthy = response.is_ok_and(|response| response.status().is_success());
+
+                    if upstream.healthy.swap(healthy, Ordering::Relaxed) == healthy {
+                        continue
+                    }
+
+                    if healthy {
+                        log::info!("upstream {} is ready", upstream.address);
+                    } else {
+                        log::warn!("upstream {} is down", upstream.address);
+                    }
+                }
+            }
+        });
+    }
+}
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        self.upstreams.inner[self.index].active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Keep-alive comes from awc's connection pool, so each worker holds one
+// client per upstream for its whole life.
+pub fn client(address: &str, timeout: Duration) -> Client {
+    let builder = Client::builder().timeout(timeout).no_default_headers();
+
+    let Some(path) = unix_socket::path(address) else {
+        return builder.finish()
+    };
+
+    let path = path.to_string();
+    let connector = Connector::new().connector(fn_service(move |connect: ConnectInfo<Uri>| {
+        let path = path.clone();
+        async move {
+            let stream = UnixStream::connect(path).await.map_err(ConnectError::Io)?;
+            Ok::<_, ConnectError>(Connection::new(connect.request().clone(), stream))
+        }
+    }));
+
+    builder.connector(connector).finish()
+}
+
+// unix upstreams still need an authority in the request uri
+pub fn base_url(address: &str) -> String {
+    match unix_socket::path(address) {
+        Some(_) => String::from("http://localhost"),
+        None => format!("http://{}", address),
+    }
+}
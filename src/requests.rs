@@ -1,4 +1,22 @@
 mod transaction;
+mod schedule;
+mod history;
+mod statement;
+mod spending_rule;
+mod fee;
+mod webhook;
+mod socket;
+mod batch;
+mod v2;
 
 pub use transaction::TransactionPayload;
+pub use schedule::SchedulePayload;
+pub use history::HistoryQuery;
+pub use statement::StatementQuery;
+pub use spending_rule::SpendingRulesPayload;
+pub use fee::FeeSimulationQuery;
+pub use webhook::{WebhookPayload, ReplayQuery};
+pub use socket::TransactionCommand;
+pub use batch::{BatchMode, BatchQuery};
+pub use v2::{TransactionPayloadV2, HistoryQueryV2};
 
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Default, ToSchema)]
+pub enum BatchMode {
+    #[default]
+    #[serde(rename = "atomico")]
+    Atomic,
+    #[serde(rename = "parcial")]
+    Partial,
+}
+
+#[derive(Deserialize, Serialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BatchQuery {
+
+    #[serde(rename = "modo", default)]
+    pub mode: BatchMode,
+
+}
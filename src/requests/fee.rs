@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+use validator::{Validate};
+
+#[derive(Validate, Deserialize, Serialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FeeSimulationQuery {
+
+    #[validate(range(min=1, max=366))]
+    #[serde(rename = "dias", default = "default_simulation_days")]
+    pub days: i64,
+
+    #[validate(range(min=0, max=10000))]
+    #[serde(rename = "debitos", default)]
+    pub debits: i64,
+
+}
+
+fn default_simulation_days() -> i64 {
+    30
+}
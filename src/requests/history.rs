@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+use validator::{Validate};
+
+use crate::models::transaction::TransactionFilter;
+
+const DEFAULT_HISTORY_LIMIT: i64 = 50;
+
+#[derive(Validate, Deserialize, Serialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+
+    #[validate(length(min=1, max=20))]
+    #[serde(rename = "categoria", default)]
+    pub category: Option<String>,
+
+    #[validate(length(min=1, max=20))]
+    #[serde(default)]
+    pub tag: Option<String>,
+
+    // `chave:valor`, matched against the metadata entry with that key
+    #[validate(contains(pattern = ":"))]
+    #[serde(default)]
+    pub metadata: Option<String>,
+
+    #[validate(range(min=1, max=1000))]
+    #[serde(rename = "limite", default = "default_history_limit")]
+    pub limit: i64,
+
+}
+
+pub(super) fn default_history_limit() -> i64 {
+    DEFAULT_HISTORY_LIMIT
+}
+
+impl HistoryQuery {
+    pub fn to_filter(&self) -> TransactionFilter {
+        let (metadata_key, metadata_value) = match &self.metadata {
+            Some(metadata) => {
+                let (key, value) = metadata.split_once(':').unwrap();
+                (Some(key.to_string()), Some(value.to_string()))
+            }
+            None => (None, None),
+        };
+
+        TransactionFilter{
+            category: self.category.clone(),
+            tag: self.tag.clone(),
+            metadata_key,
+            metadata_value,
+            limit: self.limit,
+        }
+    }
+}
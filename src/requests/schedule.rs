@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::models::{Recurrence, Schedule};
+use super::transaction::validate_transaction_type;
+
+#[derive(Validate, Deserialize, Serialize, Clone, ToSchema)]
+pub struct SchedulePayload {
+
+    #[validate(range(min=1))]
+    #[serde(rename = "valor")]
+    pub amount: i64,
+
+    #[validate(custom(function = "validate_transaction_type"))]
+    #[serde(rename = "tipo")]
+    #[schema(value_type = String, pattern = "^[cd]$")]
+    pub transaction_type: char,
+
+    #[validate(length(min=1, max=10))]
+    #[serde(rename = "descricao")]
+    pub description: String,
+
+    #[validate(custom(function = "validate_run_at"))]
+    #[serde(rename = "executar_em")]
+    pub run_at: DateTime<Utc>,
+
+    #[serde(rename = "recorrencia", default)]
+    pub recurrence: Recurrence,
+
+}
+
+fn validate_run_at(value: &DateTime<Utc>) -> Result<(), ValidationError> {
+    if *value < Utc::now() {
+        return Err(ValidationError::new("PAST_DATE"))
+    }
+
+    Ok(())
+}
+
+impl SchedulePayload {
+    pub fn to_model(&self, customer_id: i32) -> Schedule {
+        Schedule{
+            id: 0,
+            customer_id,
+            amount: self.amount,
+            transaction_type: String::from(self.transaction_type),
+            description: self.description.clone(),
+            recurrence: self.recurrence,
+            next_run_at: self.run_at,
+            anchor_at: self.run_at,
+            attempts: 0,
+        }
+    }
+}
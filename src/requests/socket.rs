@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+use super::transaction::TransactionPayload;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct TransactionCommand {
+
+    pub id: String,
+
+    #[serde(flatten)]
+    pub payload: TransactionPayload,
+
+}
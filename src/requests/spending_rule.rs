@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::models::SpendingRules;
+
+const MAX_BLOCKED_PATTERNS: usize = 20;
+const MAX_PATTERN_LENGTH: usize = 64;
+
+#[derive(Validate, Deserialize, Serialize, Clone, ToSchema)]
+pub struct SpendingRulesPayload {
+
+    #[validate(range(min=1))]
+    #[serde(rename = "debito_maximo", default)]
+    pub max_debit: Option<i64>,
+
+    #[validate(range(min=1))]
+    #[serde(rename = "debito_diario_maximo", default)]
+    pub max_daily_debit: Option<i64>,
+
+    #[validate(range(min=1))]
+    #[serde(rename = "debitos_por_hora_maximo", default)]
+    pub max_hourly_debits: Option<i32>,
+
+    #[validate(custom(function = "validate_patterns"))]
+    #[serde(rename = "descricoes_bloqueadas", default)]
+    pub blocked_patterns: Vec<String>,
+
+}
+
+fn validate_patterns(value: &[String]) -> Result<(), ValidationError> {
+    if value.len() > MAX_BLOCKED_PATTERNS {
+        return Err(ValidationError::new("TOO_MANY_PATTERNS"))
+    }
+
+    let invalid_pattern = value.iter().any(|pattern| pattern.is_empty() || pattern.chars().count() > MAX_PATTERN_LENGTH);
+    if invalid_pattern {
+        return Err(ValidationError::new("INVALID_PATTERN"))
+    }
+
+    Ok(())
+}
+
+impl SpendingRulesPayload {
+    pub fn to_model(&self) -> SpendingRules {
+        SpendingRules{
+            max_debit: self.max_debit,
+            max_daily_debit: self.max_daily_debit,
+            max_hourly_debits: self.max_hourly_debits,
+            blocked_patterns: self.blocked_patterns.clone(),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+use validator::{Validate, ValidationError};
+
+use crate::models::StatementPeriod;
+
+#[derive(Validate, Deserialize, Serialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[validate(schema(function = "validate_period"))]
+pub struct StatementQuery {
+
+    #[serde(rename = "de", default)]
+    pub start: Option<NaiveDate>,
+
+    #[serde(rename = "ate", default)]
+    pub end: Option<NaiveDate>,
+
+}
+
+// four digit years only, which postgres and the exports can represent and
+// which leave the end of the period a next day to stop at
+fn validate_period(query: &StatementQuery) -> Result<(), ValidationError> {
+    let in_range = |date: NaiveDate| (1..=9999).contains(&date.year());
+
+    match (query.start, query.end) {
+        (None, None) => Ok(()),
+        (Some(start), Some(end)) if start <= end && in_range(start) && in_range(end) => Ok(()),
+        _ => Err(ValidationError::new("INVALID_PERIOD")),
+    }
+}
+
+impl StatementQuery {
+    pub fn to_period(&self) -> Option<StatementPeriod> {
+        Some(StatementPeriod{
+            start: self.start?,
+            end: self.end?,
+        })
+    }
+}
@@ -1,27 +1,49 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+use utoipa::ToSchema;
 use validator::{Validate, ValidationError};
 
 use crate::models::{Transaction};
 
-#[derive(Validate, Deserialize, Serialize, Clone)]
+#[derive(Validate, Deserialize, Serialize, Clone, ToSchema)]
 pub struct TransactionPayload {
 
     #[validate(range(min=1))]
-    #[serde(rename(deserialize = "valor"))]
+    #[serde(rename = "valor")]
     pub amount: i64,
 
     #[validate(custom(function = "validate_transaction_type"))]
-    #[serde(rename(deserialize = "tipo"))]
+    #[serde(rename = "tipo")]
+    #[schema(value_type = String, pattern = "^[cd]$")]
     pub transaction_type: char,
 
     #[validate(length(min=1, max=10))]
-    #[serde(rename(deserialize = "descricao"))]
+    #[serde(rename = "descricao")]
     pub description: String,
 
+    #[validate(length(min=1, max=20))]
+    #[serde(rename = "categoria", default)]
+    pub category: Option<String>,
+
+    #[validate(custom(function = "validate_tags"))]
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[validate(custom(function = "validate_metadata"))]
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub metadata: Option<Map<String, Value>>,
+
 }
 
-fn validate_transaction_type(value: &char) -> Result<(), ValidationError> {
+const MAX_TAGS: usize = 5;
+const MAX_TAG_LENGTH: usize = 20;
+const MAX_METADATA_KEYS: usize = 10;
+const MAX_METADATA_BYTES: usize = 512;
+
+pub fn validate_transaction_type(value: &char) -> Result<(), ValidationError> {
     match value {
         'c' => Ok(()),
         'd' => Ok(()),
@@ -29,13 +51,52 @@ fn validate_transaction_type(value: &char) -> Result<(), ValidationError> {
     }
 }
 
+pub fn validate_tags(value: &[String]) -> Result<(), ValidationError> {
+    if value.len() > MAX_TAGS {
+        return Err(ValidationError::new("TOO_MANY_TAGS"))
+    }
+
+    let invalid_tag = value.iter().any(|tag| tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH);
+    if invalid_tag {
+        return Err(ValidationError::new("INVALID_TAG"))
+    }
+
+    Ok(())
+}
+
+// metadata is a flat object of scalars, bounded in keys and encoded size
+pub fn validate_metadata(value: &Option<Map<String, Value>>) -> Result<(), ValidationError> {
+    let Some(value) = value else {
+        return Ok(())
+    };
+
+    if value.len() > MAX_METADATA_KEYS {
+        return Err(ValidationError::new("TOO_MANY_METADATA_KEYS"))
+    }
+
+    let nested = value.values().any(|item| item.is_object() || item.is_array());
+    if nested {
+        return Err(ValidationError::new("NESTED_METADATA"))
+    }
+
+    if serde_json::to_vec(value).unwrap().len() > MAX_METADATA_BYTES {
+        return Err(ValidationError::new("METADATA_TOO_LARGE"))
+    }
+
+    Ok(())
+}
+
 impl TransactionPayload {
     pub fn to_model(&self, customer_id: i64, created_at: DateTime<Utc>) -> Transaction {
         Transaction{
+            id: Uuid::new_v4(),
             customer_id,
             amount: self.amount,
             transaction_type: String::from(self.transaction_type),
             description: self.description.clone(),
+            category: self.category.clone(),
+            tags: self.tags.clone(),
+            metadata: self.metadata.clone().map(Value::Object),
             created_at,
         }
     }
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::{IntoParams, ToSchema};
+
+use super::history::{default_history_limit, HistoryQuery};
+use super::transaction::TransactionPayload;
+
+// The /v2 contract only renames fields; validation stays on the v1 types.
+#[derive(Deserialize, Serialize, Clone, ToSchema)]
+pub struct TransactionPayloadV2 {
+    pub amount: i64,
+    #[serde(rename = "type")]
+    #[schema(value_type = String, pattern = "^[cd]$")]
+    pub transaction_type: char,
+    pub description: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub metadata: Option<Map<String, Value>>,
+}
+
+impl TransactionPayloadV2 {
+    pub fn to_payload(&self) -> TransactionPayload {
+        TransactionPayload{
+            amount: self.amount,
+            transaction_type: self.transaction_type,
+            description: self.description.clone(),
+            category: self.category.clone(),
+            tags: self.tags.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQueryV2 {
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    // `key:value`, matched against the metadata entry with that key
+    #[serde(default)]
+    pub metadata: Option<String>,
+    #[serde(default = "default_history_limit")]
+    pub limit: i64,
+}
+
+impl HistoryQueryV2 {
+    pub fn to_query(&self) -> HistoryQuery {
+        HistoryQuery{
+            category: self.category.clone(),
+            tag: self.tag.clone(),
+            metadata: self.metadata.clone(),
+            limit: self.limit,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate};
+
+use crate::models::Webhook;
+
+#[derive(Validate, Deserialize, Serialize, Clone, ToSchema)]
+pub struct WebhookPayload {
+
+    #[validate(url, length(max=512))]
+    pub url: String,
+
+    #[validate(length(min=16, max=128))]
+    #[serde(rename = "segredo")]
+    pub secret: String,
+
+}
+
+impl WebhookPayload {
+    pub fn to_model(&self) -> Webhook {
+        Webhook{
+            url: self.url.clone(),
+            secret: self.secret.clone(),
+        }
+    }
+}
+
+#[derive(Validate, Deserialize, Serialize, Clone)]
+pub struct ReplayQuery {
+
+    #[validate(range(min=1))]
+    #[serde(default)]
+    pub id: Option<i64>,
+
+    // dead letter ids are per shard
+    #[serde(default)]
+    pub shard: Option<usize>,
+
+}
@@ -1,3 +1,32 @@
 mod transaction;
+mod schedule;
+mod history;
+mod statement;
+mod spending_rule;
+mod fee;
+mod event;
+mod socket;
+mod batch;
+mod v2;
+mod cache;
 
-pub use transaction::{CreateTransactionResponse, GetStatementResponse};
\ No newline at end of file
+pub use transaction::{
+    CreateTransactionResponse, GetStatementResponse, GetStatementBalanceResponse,
+    GetStatementTransactionsCacheResponse, RawStatementResponse,
+};
+pub use schedule::CreateScheduleResponse;
+pub use history::GetHistoryResponse;
+pub use statement::{
+    GetPeriodStatementResponse, GetPeriodStatementBalanceResponse,
+    GetPeriodStatementPeriodResponse, GetPeriodStatementTotalsResponse,
+};
+pub use spending_rule::{SpendingRulesResponse, RuleViolationResponse};
+pub use fee::{FeeSimulationResponse, SimulatedDayResponse};
+pub use event::{TransactionEventResponse, DeadLetterResponse, ReplayResponse, ApiKeyResponse};
+pub use socket::{CommandResultResponse, BalanceUpdateResponse};
+pub use batch::{BatchResponse, BatchItemResponse, BatchFailureResponse};
+pub use cache::CacheStatsResponse;
+pub use v2::{
+    CreateTransactionResponseV2, GetStatementResponseV2, StatementBalanceResponseV2,
+    TransactionResponseV2, GetHistoryResponseV2, ErrorResponseV2,
+};
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::errors::Error;
+use crate::models::transaction::CustomerLean;
+use crate::requests::BatchMode;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BatchItemResponse {
+    #[serde(rename = "indice")]
+    pub index: usize,
+    pub ok: bool,
+    #[serde(rename = "limite", skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(rename = "saldo", skip_serializing_if = "Option::is_none")]
+    pub balance: Option<i64>,
+    #[serde(rename = "erro", skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchItemResponse {
+    pub fn from_result(index: usize, result: &Result<CustomerLean, Error>) -> BatchItemResponse {
+        match result {
+            Ok(customer) => BatchItemResponse{
+                index,
+                ok: true,
+                limit: Some(customer.limit),
+                balance: Some(customer.balance),
+                error: None,
+            },
+            Err(err) => BatchItemResponse{
+                index,
+                ok: false,
+                limit: None,
+                balance: None,
+                error: Some(err.code().to_string()),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BatchResponse {
+    #[serde(rename = "modo")]
+    pub mode: BatchMode,
+    #[serde(rename = "limite", skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(rename = "saldo", skip_serializing_if = "Option::is_none")]
+    pub balance: Option<i64>,
+    #[serde(rename = "resultados")]
+    pub results: Vec<BatchItemResponse>,
+}
+
+impl BatchResponse {
+    pub fn from_results(mode: BatchMode, results: &[Result<CustomerLean, Error>]) -> BatchResponse {
+        let last = results.iter().rev().find_map(|result| result.as_ref().ok());
+
+        BatchResponse{
+            mode,
+            limit: last.map(|customer| customer.limit),
+            balance: last.map(|customer| customer.balance),
+            results: results
+                .iter()
+                .enumerate()
+                .map(|(index, result)| BatchItemResponse::from_result(index, result))
+                .collect(),
+        }
+    }
+}
+
+// an atomic batch is all-or-nothing, so only the item that aborted it is reported
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BatchFailureResponse {
+    #[serde(rename = "indice")]
+    pub index: usize,
+    #[serde(rename = "erro")]
+    pub error: String,
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cache::CacheStats;
+
+#[derive(Deserialize, Serialize)]
+pub struct CacheStatsResponse {
+    #[serde(rename(serialize = "entradas"))]
+    pub entries: usize,
+    #[serde(rename(serialize = "acertos"))]
+    pub hits: u64,
+    #[serde(rename(serialize = "faltas"))]
+    pub misses: u64,
+    #[serde(rename(serialize = "taxa_acerto"))]
+    pub hit_rate: f64,
+}
+
+impl CacheStatsResponse {
+    pub fn from_stats(stats: &CacheStats) -> CacheStatsResponse {
+        let lookups = stats.hits + stats.misses;
+
+        CacheStatsResponse{
+            entries: stats.entries,
+            hits: stats.hits,
+            misses: stats.misses,
+            hit_rate: if lookups == 0 { 0.0 } else { stats.hits as f64 / lookups as f64 },
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::{DeadLetter, TransactionEvent};
+use crate::serializers::rinha_date_format;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct TransactionEventResponse {
+    pub id: i64,
+    #[serde(rename = "cliente_id")]
+    pub customer_id: i64,
+    #[serde(rename = "valor")]
+    pub amount: i64,
+    #[serde(rename = "tipo")]
+    pub transaction_type: String,
+    #[serde(rename = "descricao")]
+    pub description: String,
+    #[serde(rename = "realizada_em", with = "rinha_date_format")]
+    pub created_at: chrono::DateTime<Utc>,
+    #[serde(rename = "limite")]
+    pub limit: i64,
+    #[serde(rename = "saldo")]
+    pub balance: i64,
+}
+
+impl TransactionEventResponse {
+    pub fn from_model(id: i64, event: &TransactionEvent) -> TransactionEventResponse {
+        TransactionEventResponse{
+            id,
+            customer_id: event.customer_id,
+            amount: event.amount,
+            transaction_type: event.transaction_type.clone(),
+            description: event.description.clone(),
+            created_at: event.created_at,
+            limit: event.limit,
+            balance: event.balance,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DeadLetterResponse {
+    pub shard: usize,
+    pub id: i64,
+    #[serde(rename(serialize = "evento_id"))]
+    pub event_id: i64,
+    #[serde(rename(serialize = "cliente_id"))]
+    pub customer_id: i32,
+    #[serde(rename(serialize = "tentativas"))]
+    pub attempts: i32,
+    #[serde(rename(serialize = "ultimo_erro"))]
+    pub last_error: Option<String>,
+    #[serde(rename(serialize = "falhou_em"), with = "rinha_date_format")]
+    pub failed_at: chrono::DateTime<Utc>,
+}
+
+impl DeadLetterResponse {
+    pub fn from_model(dead_letter: &DeadLetter) -> DeadLetterResponse {
+        DeadLetterResponse{
+            shard: dead_letter.shard,
+            id: dead_letter.id,
+            event_id: dead_letter.event_id,
+            customer_id: dead_letter.customer_id,
+            attempts: dead_letter.attempts,
+            last_error: dead_letter.last_error.clone(),
+            failed_at: dead_letter.failed_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ReplayResponse {
+    #[serde(rename(serialize = "reprocessados"))]
+    pub replayed: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiKeyResponse {
+    #[serde(rename(serialize = "chave"))]
+    pub token: String,
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::{FeePolicy, FeeSimulation};
+use crate::models::fee::SimulatedDay;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SimulatedDayResponse {
+    #[serde(rename = "dia")]
+    pub day: i64,
+    #[serde(rename = "juros")]
+    pub interest: i64,
+    #[serde(rename = "saldo")]
+    pub balance: i64,
+}
+
+impl SimulatedDayResponse {
+    pub fn from_model(day: &SimulatedDay) -> SimulatedDayResponse {
+        SimulatedDayResponse{
+            day: day.day,
+            interest: day.interest,
+            balance: day.balance,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct FeeSimulationResponse {
+    #[serde(rename = "juros_diario_bps")]
+    pub daily_interest_bps: i64,
+    #[serde(rename = "tarifa_debito")]
+    pub debit_fee: i64,
+    #[serde(rename = "saldo_inicial")]
+    pub starting_balance: i64,
+    #[serde(rename = "limite")]
+    pub limit: i64,
+    #[serde(rename = "total_juros")]
+    pub total_interest: i64,
+    #[serde(rename = "total_tarifas")]
+    pub total_fees: i64,
+    #[serde(rename = "saldo_final")]
+    pub final_balance: i64,
+    #[serde(rename = "excede_limite")]
+    pub exceeds_limit: bool,
+    #[serde(rename = "dias")]
+    pub days: Vec<SimulatedDayResponse>,
+}
+
+impl FeeSimulationResponse {
+    pub fn from_model(policy: &FeePolicy, simulation: &FeeSimulation) -> FeeSimulationResponse {
+        FeeSimulationResponse{
+            daily_interest_bps: policy.daily_interest_bps,
+            debit_fee: policy.debit_fee,
+            starting_balance: simulation.starting_balance,
+            limit: simulation.limit,
+            total_interest: simulation.total_interest,
+            total_fees: simulation.total_fees,
+            final_balance: simulation.final_balance,
+            exceeds_limit: simulation.exceeds_limit,
+            days: simulation.days.iter().map(SimulatedDayResponse::from_model).collect(),
+        }
+    }
+}
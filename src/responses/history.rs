@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::TransactionCache;
+use super::transaction::GetStatementTransactionsCacheResponse;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct GetHistoryResponse {
+    #[serde(rename = "transacoes")]
+    pub transactions: Vec<GetStatementTransactionsCacheResponse>,
+}
+
+impl GetHistoryResponse {
+    pub fn from_models(transactions: &[TransactionCache]) -> GetHistoryResponse {
+        GetHistoryResponse{
+            transactions: transactions
+                .iter()
+                .map(GetStatementTransactionsCacheResponse::from_model_cache)
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::{Recurrence, Schedule};
+use crate::serializers::rinha_date_format;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct CreateScheduleResponse {
+    pub id: i64,
+    #[serde(rename = "recorrencia")]
+    pub recurrence: Recurrence,
+    #[serde(rename = "proxima_execucao", with = "rinha_date_format")]
+    pub next_run_at: chrono::DateTime<Utc>,
+}
+
+impl CreateScheduleResponse {
+    pub fn from_model(schedule: &Schedule) -> CreateScheduleResponse {
+        CreateScheduleResponse{
+            id: schedule.id,
+            recurrence: schedule.recurrence,
+            next_run_at: schedule.next_run_at,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+use crate::models::TransactionNotification;
+use crate::models::transaction::CustomerLean;
+use super::event::TransactionEventResponse;
+
+#[derive(Deserialize, Serialize)]
+pub struct CommandResultResponse {
+    pub id: Option<String>,
+    pub ok: bool,
+    #[serde(rename(serialize = "limite"), skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(rename(serialize = "saldo"), skip_serializing_if = "Option::is_none")]
+    pub balance: Option<i64>,
+    #[serde(rename(serialize = "erro"), skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CommandResultResponse {
+    pub fn from_result(id: Option<String>, result: &Result<CustomerLean, Error>) -> CommandResultResponse {
+        match result {
+            Ok(customer) => CommandResultResponse{
+                id,
+                ok: true,
+                limit: Some(customer.limit),
+                balance: Some(customer.balance),
+                error: None,
+            },
+            Err(err) => CommandResultResponse::from_error(id, err.code()),
+        }
+    }
+
+    pub fn from_error(id: Option<String>, code: &str) -> CommandResultResponse {
+        CommandResultResponse{
+            id,
+            ok: false,
+            limit: None,
+            balance: None,
+            error: Some(code.to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct BalanceUpdateResponse {
+    #[serde(rename(serialize = "evento"))]
+    pub event: String,
+    #[serde(rename(serialize = "transacao"))]
+    pub transaction: TransactionEventResponse,
+}
+
+impl BalanceUpdateResponse {
+    pub fn from_notification(notification: &TransactionNotification) -> BalanceUpdateResponse {
+        BalanceUpdateResponse{
+            event: String::from("saldo"),
+            transaction: TransactionEventResponse::from_model(notification.seq, &notification.event),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::{SpendingRule, SpendingRules};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SpendingRulesResponse {
+    #[serde(rename = "debito_maximo")]
+    pub max_debit: Option<i64>,
+    #[serde(rename = "debito_diario_maximo")]
+    pub max_daily_debit: Option<i64>,
+    #[serde(rename = "debitos_por_hora_maximo")]
+    pub max_hourly_debits: Option<i32>,
+    #[serde(rename = "descricoes_bloqueadas")]
+    pub blocked_patterns: Vec<String>,
+}
+
+impl SpendingRulesResponse {
+    pub fn from_model(rules: &SpendingRules) -> SpendingRulesResponse {
+        SpendingRulesResponse{
+            max_debit: rules.max_debit,
+            max_daily_debit: rules.max_daily_debit,
+            max_hourly_debits: rules.max_hourly_debits,
+            blocked_patterns: rules.blocked_patterns.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RuleViolationResponse {
+    #[serde(rename = "erro")]
+    pub code: String,
+}
+
+impl RuleViolationResponse {
+    pub fn from_rule(rule: SpendingRule) -> RuleViolationResponse {
+        RuleViolationResponse{
+            code: rule.code().to_string(),
+        }
+    }
+}
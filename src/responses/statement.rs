@@ -0,0 +1,72 @@
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::PeriodStatement;
+use crate::serializers::rinha_date_format;
+use super::transaction::GetStatementTransactionsCacheResponse;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct GetPeriodStatementBalanceResponse {
+    #[serde(rename = "inicial")]
+    pub opening_balance: i64,
+    #[serde(rename = "final")]
+    pub closing_balance: i64,
+    #[serde(rename = "data_extrato", with = "rinha_date_format")]
+    pub date: chrono::DateTime<Utc>,
+    #[serde(rename = "limite")]
+    pub limit: i64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct GetPeriodStatementPeriodResponse {
+    #[serde(rename = "de")]
+    pub start: NaiveDate,
+    #[serde(rename = "ate")]
+    pub end: NaiveDate,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct GetPeriodStatementTotalsResponse {
+    #[serde(rename = "creditos")]
+    pub credits: i64,
+    #[serde(rename = "debitos")]
+    pub debits: i64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct GetPeriodStatementResponse {
+    #[serde(rename = "saldo")]
+    pub balance: GetPeriodStatementBalanceResponse,
+    #[serde(rename = "periodo")]
+    pub period: GetPeriodStatementPeriodResponse,
+    #[serde(rename = "totais")]
+    pub totals: GetPeriodStatementTotalsResponse,
+    #[serde(rename = "transacoes")]
+    pub transactions: Vec<GetStatementTransactionsCacheResponse>,
+}
+
+impl GetPeriodStatementResponse {
+    pub fn from_model(statement: &PeriodStatement) -> GetPeriodStatementResponse {
+        GetPeriodStatementResponse{
+            balance: GetPeriodStatementBalanceResponse{
+                opening_balance: statement.opening_balance,
+                closing_balance: statement.closing_balance,
+                date: Utc::now(),
+                limit: statement.limit,
+            },
+            period: GetPeriodStatementPeriodResponse{
+                start: statement.period.start,
+                end: statement.period.end,
+            },
+            totals: GetPeriodStatementTotalsResponse{
+                credits: statement.total_credits,
+                debits: statement.total_debits,
+            },
+            transactions: statement.transactions
+                .iter()
+                .map(GetStatementTransactionsCacheResponse::from_model_cache)
+                .collect(),
+        }
+    }
+}
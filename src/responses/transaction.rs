@@ -1,15 +1,18 @@
+use std::borrow::Cow;
+
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::models::transaction::{Customer, CustomerLean};
 use crate::models::TransactionCache;
 use crate::serializers::rinha_date_format;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct CreateTransactionResponse {
-    #[serde(rename(serialize = "limite"))]
+    #[serde(rename = "limite")]
     pub limit: i64,
-    #[serde(rename(serialize = "saldo"))]
+    #[serde(rename = "saldo")]
     pub balance: i64,
 }
 
@@ -22,13 +25,13 @@ impl CreateTransactionResponse {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
 pub struct GetStatementBalanceResponse{
-    #[serde(rename(serialize = "total"))]
+    #[serde(rename = "total")]
     pub balance: i64,
-    #[serde(rename(serialize = "data_extrato"), with = "rinha_date_format")]
+    #[serde(rename = "data_extrato", with = "rinha_date_format")]
     pub date: chrono::DateTime<Utc>,
-    #[serde(rename(serialize = "limite"))]
+    #[serde(rename = "limite")]
     pub limit: i64,
 }
 
@@ -42,15 +45,23 @@ impl GetStatementBalanceResponse {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
 pub struct GetStatementTransactionsCacheResponse {
-    #[serde(rename(serialize = "valor"))]
+    #[serde(rename = "valor")]
     pub amount: i64,
-    #[serde(rename(serialize = "tipo"))]
+    #[serde(rename = "tipo")]
+    #[schema(value_type = String)]
     pub transaction_type: char,
-    #[serde(rename(serialize = "descricao"))]
+    #[serde(rename = "descricao")]
     pub description: String,
-    #[serde(rename(serialize = "realizada_em"), with = "rinha_date_format")]
+    #[serde(rename = "categoria", skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub metadata: Option<serde_json::Value>,
+    #[serde(rename = "realizada_em", with = "rinha_date_format")]
     pub created_at: chrono::DateTime<Utc>,
 }
 
@@ -62,16 +73,19 @@ impl GetStatementTransactionsCacheResponse {
             amount: transaction_cache.amount,
             transaction_type: transaction_cache.transaction_type.parse().unwrap(),
             description: transaction_cache.description.clone(),
+            category: transaction_cache.category.clone(),
+            tags: transaction_cache.tags.clone(),
+            metadata: transaction_cache.metadata.clone(),
             created_at: transaction_cache.created_at,
         }
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema, Clone)]
 pub struct GetStatementResponse {
-    #[serde(rename(serialize = "saldo"))]
+    #[serde(rename = "saldo")]
     pub balance: GetStatementBalanceResponse,
-    #[serde(rename(serialize = "ultimas_transacoes"))]
+    #[serde(rename = "ultimas_transacoes")]
     pub transactions_cache: Vec<GetStatementTransactionsCacheResponse>,
 }
 
@@ -79,14 +93,99 @@ impl GetStatementResponse {
     pub fn from_customer(customer: &Customer) -> GetStatementResponse {
         let transactions_cache = customer.transactions
             .iter()
-            .map(|transaction| GetStatementTransactionsCacheResponse::from_model_cache(transaction))
+            .map(GetStatementTransactionsCacheResponse::from_model_cache)
             .collect();
 
         let balance = GetStatementBalanceResponse::from_model(customer);
 
-        return GetStatementResponse{
+        GetStatementResponse{
             balance,
             transactions_cache,
         }
     }
 }
+
+// One latest_transactions entry read in place from the stored text and written
+// back under the GetStatementTransactionsCacheResponse names: strings borrow
+// from the text unless they hold escapes and the date is copied as stored.
+#[derive(Deserialize, Serialize)]
+struct RawStatementEntry<'a> {
+    #[serde(rename(serialize = "valor"))]
+    amount: i64,
+    #[serde(rename(serialize = "tipo"), borrow)]
+    transaction_type: Cow<'a, str>,
+    #[serde(rename(serialize = "descricao"), borrow)]
+    description: Cow<'a, str>,
+    #[serde(rename(serialize = "categoria"), default, borrow, skip_serializing_if = "Option::is_none")]
+    category: Option<Cow<'a, str>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<serde_json::Value>,
+    #[serde(rename(serialize = "realizada_em"), borrow)]
+    created_at: Cow<'a, str>,
+}
+
+// GetStatementResponse without the typed entries, for the raw statement path
+#[derive(Serialize)]
+pub struct RawStatementResponse<'a> {
+    #[serde(rename = "saldo")]
+    balance: GetStatementBalanceResponse,
+    #[serde(rename = "ultimas_transacoes")]
+    transactions: Vec<RawStatementEntry<'a>>,
+}
+
+impl RawStatementResponse<'_> {
+    pub fn render(limit: i64, balance: i64, latest_transactions: &str) -> serde_json::Result<String> {
+        let response = RawStatementResponse{
+            balance: GetStatementBalanceResponse{
+                balance,
+                date: Utc::now(),
+                limit,
+            },
+            transactions: serde_json::from_str(latest_transactions)?,
+        };
+
+        serde_json::to_string(&response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // data_extrato is taken at render time, so it can't match between two renders
+    fn without_date(body: &str) -> String {
+        let start = body.find("\"data_extrato\":\"").unwrap() + "\"data_extrato\":\"".len();
+        let end = start + body[start..].find('"').unwrap();
+
+        format!("{}{}", &body[..start], &body[end..])
+    }
+
+    #[test]
+    fn raw_statement_matches_serde() {
+        // latest_transactions as postgres prints jsonb: sorted keys, spaces
+        // between tokens, escapes in strings and an entry without the optionals
+        let latest_transactions = r#"[{"id": "4b1a3c2e-8f0e-4c5a-9a59-0d5d7c1e2f3a", "tags": ["x y", "z"], "amount": 1, "category": "teste", "metadata": {"b": "c  d", "aa": true, "zz": {"a": null, "b": [1, 2.5]}}, "created_at": "2026-10-19T08:10:38.866962Z", "description": "a \"b\" \u0001 ç", "transaction_type": "c"}, {"amount": 2, "created_at": "2026-10-19T08:10:37.000001Z", "description": "simples", "transaction_type": "d"}]"#;
+
+        let raw = RawStatementResponse::render(1000, -1, latest_transactions).unwrap();
+
+        let customer = Customer{
+            limit: 1000,
+            balance: -1,
+            transactions: serde_json::from_str(latest_transactions).unwrap(),
+        };
+        let serde = serde_json::to_string(&GetStatementResponse::from_customer(&customer)).unwrap();
+
+        assert_eq!(without_date(&raw), without_date(&serde));
+        assert!(raw.contains(r#""realizada_em":"2026-10-19T08:10:37.000001Z"}]"#));
+    }
+
+    #[test]
+    fn raw_statement_of_a_customer_without_transactions() {
+        let raw = RawStatementResponse::render(500, 0, "[]").unwrap();
+
+        assert!(raw.starts_with(r#"{"saldo":{"total":0,"data_extrato":""#));
+        assert!(raw.ends_with(r#""limite":500},"ultimas_transacoes":[]}"#));
+    }
+}
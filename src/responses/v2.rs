@@ -0,0 +1,119 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::errors::Error;
+use crate::models::TransactionCache;
+use crate::models::transaction::{Customer, CustomerLean};
+use crate::serializers::rinha_date_format;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct CreateTransactionResponseV2 {
+    pub limit: i64,
+    pub balance: i64,
+    // what is left to spend, counting the credit limit
+    pub available: i64,
+}
+
+impl CreateTransactionResponseV2 {
+    pub fn from_model(customer: &CustomerLean) -> CreateTransactionResponseV2 {
+        CreateTransactionResponseV2{
+            limit: customer.limit,
+            balance: customer.balance,
+            available: customer.limit + customer.balance,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct StatementBalanceResponseV2 {
+    pub total: i64,
+    pub limit: i64,
+    pub available: i64,
+    #[serde(with = "rinha_date_format")]
+    pub date: chrono::DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct TransactionResponseV2 {
+    pub amount: i64,
+    #[serde(rename = "type")]
+    pub transaction_type: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub metadata: Option<serde_json::Value>,
+    #[serde(with = "rinha_date_format")]
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+impl TransactionResponseV2 {
+    pub fn from_model_cache(transaction_cache: &TransactionCache) -> TransactionResponseV2 {
+        TransactionResponseV2{
+            amount: transaction_cache.amount,
+            transaction_type: transaction_cache.transaction_type.clone(),
+            description: transaction_cache.description.clone(),
+            category: transaction_cache.category.clone(),
+            tags: transaction_cache.tags.clone(),
+            metadata: transaction_cache.metadata.clone(),
+            created_at: transaction_cache.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct GetStatementResponseV2 {
+    pub balance: StatementBalanceResponseV2,
+    pub last_transactions: Vec<TransactionResponseV2>,
+}
+
+impl GetStatementResponseV2 {
+    pub fn from_customer(customer: &Customer) -> GetStatementResponseV2 {
+        GetStatementResponseV2{
+            balance: StatementBalanceResponseV2{
+                total: customer.balance,
+                limit: customer.limit,
+                available: customer.limit + customer.balance,
+                date: Utc::now(),
+            },
+            last_transactions: customer.transactions
+                .iter()
+                .map(TransactionResponseV2::from_model_cache)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct GetHistoryResponseV2 {
+    pub transactions: Vec<TransactionResponseV2>,
+}
+
+impl GetHistoryResponseV2 {
+    pub fn from_models(transactions: &[TransactionCache]) -> GetHistoryResponseV2 {
+        GetHistoryResponseV2{
+            transactions: transactions
+                .iter()
+                .map(TransactionResponseV2::from_model_cache)
+                .collect(),
+        }
+    }
+}
+
+// v2 always says why a request failed, v1 only does it for rule violations
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ErrorResponseV2 {
+    pub error: String,
+}
+
+impl ErrorResponseV2 {
+    pub fn from_error(err: &Error) -> ErrorResponseV2 {
+        ErrorResponseV2{
+            error: err.code_v2().to_string(),
+        }
+    }
+}
@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc, NaiveDateTime};
 use serde::{self, Deserialize, Serializer, Deserializer};
 
-const FORMAT: &'static str = "%Y-%m-%dT%H:%M:%S%.6fZ";
+const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6fZ";
+
+pub fn format(date: &DateTime<Utc>) -> String {
+    format!("{}", date.format(FORMAT))
+}
 
 // The signature of a serialize_with function must follow the pattern:
 //
@@ -17,7 +21,7 @@ pub fn serialize<S>(
     where
         S: Serializer,
 {
-    let s = format!("{}", date.format(FORMAT));
+    let s = format(date);
     serializer.serialize_str(&s)
 }
 
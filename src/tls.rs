@@ -0,0 +1,32 @@
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+
+use rustls::ServerConfig;
+use rustls::crypto::ring;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::pki_types::pem::PemObject;
+
+use crate::config::Config;
+
+// SERVER_TLS_CERT and SERVER_TLS_KEY are PEM files. With both set the server
+// speaks TLS only, and ALPN lets each client pick h2 or http/1.1.
+pub fn server_config(config: &Config) -> std::io::Result<Option<ServerConfig>> {
+    let (Some(cert), Some(key)) = (&config.server_tls_cert, &config.server_tls_key) else {
+        return Ok(None)
+    };
+
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| invalid(cert, err))?;
+    let key = PrivateKeyDer::from_pem_file(key).map_err(|err| invalid(key, err))?;
+
+    ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map(Some)
+        .map_err(|err| Error::new(ErrorKind::InvalidInput, err))
+}
+
+fn invalid(path: &str, err: impl std::fmt::Display) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("{}: {}", path, err))
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::UnixStream;
+
+// SERVER_URL=unix:/path/to/nilapi.sock
+pub fn path(server_url: &str) -> Option<&str> {
+    server_url.strip_prefix("unix:")
+}
+
+// A socket file left behind by a crashed instance keeps bind from working.
+// It is removed only when nothing answers on it and it really is a socket,
+// so neither a live instance nor an unrelated file gets clobbered.
+pub fn remove_stale(path: &str) -> std::io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    if !metadata.file_type().is_socket() {
+        return Err(Error::new(ErrorKind::AlreadyExists, format!("{} exists and is not a socket", path)))
+    }
+
+    if UnixStream::connect(path).is_ok() {
+        return Err(Error::new(ErrorKind::AddrInUse, format!("another process is listening on {}", path)))
+    }
+
+    log::info!("removing stale socket {}", path);
+    fs::remove_file(path)
+}
+
+// SERVER_SOCKET_MODE is octal, as chmod takes it
+pub fn set_mode(path: &str, mode: &str) -> std::io::Result<()> {
+    let mode = u32::from_str_radix(mode, 8)
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("invalid SERVER_SOCKET_MODE {:?}", mode)))?;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}